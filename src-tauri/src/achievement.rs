@@ -0,0 +1,59 @@
+use tauri::{AppHandle, Manager, State};
+use waterer_core::{
+    achievement::{
+        unlock_achievements, Achievement, AchievementContext, AchievementRepository,
+        AchievementRules,
+    },
+    sip::SipState,
+    streak::Streaks,
+};
+
+use crate::{
+    notification::notify_achievement,
+    state::{ClockState, DatabaseState},
+};
+
+/// Wrapper for the achievement rules loaded at startup
+pub struct AchievementState(pub AchievementRules);
+
+/// Unlocks achievements reached by the latest sip and notifies about each
+pub async fn check_achievements(
+    app: &AppHandle,
+    sip_state: &SipState,
+    streaks: Option<&Streaks>,
+) -> anyhow::Result<()> {
+    let rules = app.state::<AchievementState>();
+    let db_state = app.state::<DatabaseState>();
+    let clock = app.state::<ClockState>();
+
+    let context = AchievementContext::load(
+        sip_state,
+        streaks.map(|streaks| streaks.current_days).unwrap_or(0),
+        &db_state.0,
+        clock.0.as_ref(),
+    )
+    .await?;
+
+    let unlocked = unlock_achievements(&rules.0, &db_state.0, &context, clock.0.now()).await?;
+    for rule in unlocked {
+        if let Err(e) = notify_achievement(app, &rule).await {
+            eprintln!("Failed to notify about achievement {}: {}", rule.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_achievements(
+    rules: State<'_, AchievementState>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<Achievement>, String> {
+    let unlocks = db_state
+        .0
+        .achievement_unlocks()
+        .await
+        .map_err(|e| format!("Failed to fetch achievements: {}", e))?;
+
+    Ok(rules.0.with_unlocks(&unlocks))
+}
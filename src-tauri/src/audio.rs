@@ -0,0 +1,64 @@
+use std::{borrow::Cow, io::Cursor, thread};
+
+use rodio::{Decoder, OutputStream, Sink};
+use tauri::{AppHandle, Manager};
+use waterer_core::audio::{bundled_sounds, sounds_dir, AudioAlerts, AudioSink, Sound, SoundClip};
+
+use crate::state::{AudioState, StateService};
+
+/// Plays sounds on the default output device
+pub struct RodioAudioSink;
+
+impl AudioSink for RodioAudioSink {
+    fn play(&self, clip: SoundClip, volume: f32) -> anyhow::Result<()> {
+        // Decode up front so a broken file fails here and the notification
+        // can fall back to another channel
+        let source = Decoder::new(Cursor::new(clip.data))?;
+
+        // The output stream can't be moved between threads, so each sound
+        // opens its own on a thread that lives until it finished playing
+        thread::spawn(move || {
+            if let Err(e) = play_to_end(source, volume) {
+                eprintln!("Failed to play {}: {}", clip.name, e);
+            }
+        });
+
+        Ok(())
+    }
+}
+
+fn play_to_end(source: Decoder<Cursor<Cow<'static, [u8]>>>, volume: f32) -> anyhow::Result<()> {
+    let (_stream, handle) = OutputStream::try_default()?;
+    let sink = Sink::try_new(&handle)?;
+    sink.set_volume(volume);
+    sink.append(source);
+    sink.sleep_until_end();
+
+    Ok(())
+}
+
+/// The configured sounds and volume, played on the app's output
+pub fn audio_alerts(app: &AppHandle) -> AudioAlerts {
+    let settings = app.state::<StateService>().0.snapshot().settings;
+
+    AudioAlerts::new(
+        app.state::<AudioState>().0.clone(),
+        settings.reminder_sounds,
+        settings.sound_volume_percent,
+    )
+}
+
+#[tauri::command]
+pub fn get_bundled_sounds() -> Vec<&'static str> {
+    bundled_sounds()
+}
+
+/// Plays `sound` at the configured volume, for trying it out in the settings.
+/// Files are only played from the sounds folder.
+#[tauri::command]
+pub fn preview_sound(app: AppHandle, sound: Sound) -> Result<(), String> {
+    sound
+        .validate_in(&sounds_dir())
+        .and_then(|()| audio_alerts(&app).play(&sound))
+        .map_err(|e| format!("Failed to play sound: {}", e))
+}
@@ -0,0 +1,25 @@
+use tauri::{AppHandle, Emitter};
+use waterer_core::{events::DomainEvent, repository::SipRepository, sip::Sip};
+
+use crate::state::DatabaseState;
+
+/// Sends an event to the UI and to every listener in the backend
+pub fn publish(app: &AppHandle, event: DomainEvent) {
+    if let Err(e) = app.emit(event.name(), &event) {
+        eprintln!("Failed to emit {}: {}", event.name(), e);
+    }
+}
+
+/// Publishes a stored sip, built from the wrapper given in `event`
+pub async fn publish_sip(
+    app: &AppHandle,
+    db_state: &DatabaseState,
+    sip_id: i64,
+    event: fn(Sip) -> DomainEvent,
+) {
+    match db_state.0.sip(sip_id).await {
+        Ok(Some(sip)) => publish(app, event(sip)),
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to fetch sip {}: {}", sip_id, e),
+    }
+}
@@ -0,0 +1,40 @@
+use tauri::{AppHandle, Emitter, Manager};
+use waterer_core::{
+    goal::{next_goal_notification, GOAL_REACHED_PERCENT},
+    sip::SipState,
+};
+
+use crate::{
+    notification::notify_goal,
+    state::{ClockState, DatabaseState, StateService},
+};
+
+/// Announces the daily goal, or a milestone towards it, the first time
+/// today's total crosses it
+pub async fn check_goal(app: &AppHandle, sip_state: &SipState) -> anyhow::Result<()> {
+    let settings = app.state::<StateService>().0.snapshot().settings;
+
+    let db_state = app.state::<DatabaseState>();
+    let clock = app.state::<ClockState>();
+
+    let progress = next_goal_notification(
+        &db_state.0,
+        clock.0.local_now().date_naive(),
+        sip_state.total_amount_today,
+        settings.daily_goal_ml,
+        settings.goal_milestone_notifications,
+        clock.0.now(),
+    )
+    .await?;
+
+    let Some(progress) = progress else {
+        return Ok(());
+    };
+
+    notify_goal(app, &progress).await?;
+    if progress.percent == GOAL_REACHED_PERCENT {
+        app.emit("goal-reached", &progress)?;
+    }
+
+    Ok(())
+}
@@ -1,155 +1,91 @@
-use std::{
-    env,
-    sync::{Mutex as SyncMutex, PoisonError},
-};
+use std::{env, sync::Arc};
 
-use chrono::{DateTime, Utc};
-use serde::Serialize;
-use sqlx::{Pool, Sqlite};
 use tauri::{Manager, RunEvent};
-use tokio::{sync::Mutex, time::Duration};
-mod db;
-use db::init_db;
+use tokio::{sync::watch, time::Duration};
+use waterer_core::{
+    achievement::AchievementRules,
+    app_state::AppState,
+    clock::{Clock, SystemClock},
+    db::{self, init_db},
+    events::{DomainEvent, ReminderFired},
+    message::MessageId,
+    presence::Presence,
+    repository::{DailyTotalsRepository, SessionRepository, SqliteRepository},
+    session::SessionEndReason,
+    settings::AppSettings,
+    sip::{SipSource, SipState},
+    state_actor::{LoggedSip, StateActor, StateSnapshot},
+};
+mod achievement;
+mod audio;
+mod events;
+mod goal;
 mod notification;
+mod presence;
+mod reminder;
+mod schedule;
+mod session;
 mod settings;
 mod sip;
+mod stats;
+mod streak;
+mod timer;
 mod tray;
 mod update;
 
-use uuid::Uuid;
 mod state;
-use state::{AppTimerState, SettingsState, SipTrackingState};
+use state::{AudioState, ClockState, DatabaseState, StateService};
 
 use crate::{
-    db::DatabaseState,
-    notification::notify_sip,
-    settings::{get_settings, update_settings, AppSettings},
-    sip::{get_sips, SipState},
-    tray::{create_tray, update_timer_menu_item},
+    achievement::{get_achievements, AchievementState},
+    audio::{get_bundled_sounds, preview_sound, RodioAudioSink},
+    events::{publish, publish_sip},
+    notification::notify_reminder,
+    presence::watch_presence,
+    reminder::{get_reminder_outcomes, snooze_reminder},
+    schedule::preview_schedule,
+    session::{end_session, get_sessions, get_timer_runs, run_heartbeat},
+    settings::{get_message_catalogs, get_settings, update_settings},
+    sip::{check_sip, get_recent_sips, get_sip_state, get_sips, log_sip, query_sips},
+    stats::{get_heatmap, get_statistics},
+    streak::{get_streaks, get_vacation_days, refresh_streaks, set_vacation_day},
+    tray::create_tray,
     update::update,
 };
 
-#[derive(Serialize, Clone)]
-struct AppState {
-    timer_started: bool,
-    session_id: Option<i64>,
-    session_start: DateTime<Utc>,
-}
-
-impl AppState {
-    fn new() -> Self {
-        Self {
-            timer_started: false,
-            session_id: None,
-            session_start: Utc::now(),
-        }
-    }
-
-    fn start_timer(&mut self) {
-        if !self.timer_started {
-            self.timer_started = true;
-        }
-    }
-
-    fn stop_timer(&mut self) {
-        if self.timer_started {
-            self.timer_started = false;
-        }
-    }
-
-    pub async fn init_session(&mut self, pool: &Pool<Sqlite>) -> anyhow::Result<()> {
-        let session_uuid = Uuid::new_v4().to_string();
-        let session_start_str = self.session_start.to_rfc3339();
-
-        let result = sqlx::query!(
-            "INSERT INTO sessions (session_id, session_start) VALUES (?, ?)",
-            session_uuid,
-            session_start_str
-        )
-        .execute(pool)
-        .await?;
-
-        self.session_id = Some(result.last_insert_rowid());
-
-        Ok(())
-    }
-}
-
 #[tauri::command]
-async fn toggle_timer(
-    app: tauri::AppHandle,
-    app_state: tauri::State<'_, AppTimerState>,
-    _sip_state: tauri::State<'_, SipTrackingState>,
-) -> Result<(), String> {
-    use tauri::Emitter;
-
+async fn toggle_timer(app: tauri::AppHandle) -> Result<(), String> {
     println!("toggle timer");
-    let _timer_started = {
-        let mut app_state = app_state.lock().map_err(|e| e.to_string())?;
-
-        if app_state.timer_started {
-            app_state.stop_timer();
-        } else {
-            app_state.start_timer();
-        }
-
-        // Update the tray menu item
-        if let Err(e) = update_timer_menu_item(&app, app_state.timer_started) {
-            eprintln!("Failed to update timer menu item: {}", e);
-        }
-
-        app.emit("update-app-state", app_state.clone())
-            .map_err(|e| e.to_string())?;
-
-        app_state.timer_started
-    };
+    timer::toggle_timer(&app).await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
 #[tauri::command]
-async fn get_app_state(app_state: tauri::State<'_, AppTimerState>) -> Result<AppState, String> {
-    match app_state.lock() {
-        Ok(app_state) => Ok(app_state.clone()),
-        Err(e) => Err(format!("Failed to fetch sips: {}", e)),
-    }
+async fn get_app_state(service: tauri::State<'_, StateService>) -> Result<AppState, String> {
+    Ok(service.0.snapshot().app_state)
 }
 
 #[tauri::command]
 async fn take_sip(
-    db_state: tauri::State<'_, DatabaseState>,
-    sip_state: tauri::State<'_, SipTrackingState>,
-    settings: tauri::State<'_, SettingsState>,
-    app_state: tauri::State<'_, AppTimerState>,
+    app: tauri::AppHandle,
+    amount: Option<i64>,
+    source: Option<SipSource>,
+    request_id: Option<String>,
+    confirmed: Option<bool>,
 ) -> Result<SipState, String> {
-    let pool = &db_state.0;
-
-    let sip_amount = {
-        let settings = settings.lock();
-        let settings = settings.ignore_poisoned();
-        settings.sip_amount_ml
-    };
-
-    let session_id = {
-        let state = app_state.lock().ignore_poisoned();
-        state.session_id
-    };
-
-    let session_id = match session_id {
-        Some(id) => id,
-        None => return Err("No session ID available".to_string()),
-    };
-
-    let mut locked_sip_state = sip_state.lock().await;
-
-    match locked_sip_state
-        .take_sip(sip_amount, pool, session_id)
-        .await
+    match log_sip(
+        &app,
+        amount,
+        source.unwrap_or_default(),
+        request_id.as_deref(),
+        confirmed.unwrap_or(false),
+    )
+    .await
     {
-        Ok(new_state) => {
-            let state_to_return = new_state.clone();
-            *locked_sip_state = new_state;
-            Ok(state_to_return)
+        Ok(LoggedSip::Logged { sip_state, .. } | LoggedSip::AlreadyLogged(sip_state)) => {
+            Ok(sip_state)
         }
+        Ok(LoggedSip::NeedsConfirmation(check)) => Err(check.warning().unwrap_or_default()),
         Err(e) => Err(e.to_string()),
     }
 }
@@ -166,15 +102,35 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             get_sips,
+            query_sips,
+            get_recent_sips,
+            get_sip_state,
             toggle_timer,
             take_sip,
+            check_sip,
             get_app_state,
             update_settings,
-            get_settings
+            get_settings,
+            preview_schedule,
+            get_statistics,
+            get_heatmap,
+            get_streaks,
+            get_vacation_days,
+            set_vacation_day,
+            get_achievements,
+            get_sessions,
+            get_timer_runs,
+            snooze_reminder,
+            get_reminder_outcomes,
+            get_message_catalogs,
+            get_bundled_sounds,
+            preview_sound
         ])
         .setup(|app| {
-            app.manage(SettingsState::new(AppSettings::load()));
-            app.manage(AppTimerState::new(AppState::new()));
+            let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+            app.manage(ClockState(clock.clone()));
+            app.manage(AudioState(Arc::new(RodioAudioSink)));
+            app.manage(AchievementState(AchievementRules::load()));
 
             //update check
             let app_handle_for_update = app.handle().clone();
@@ -186,37 +142,80 @@ pub fn run() {
 
             init_db();
 
+            let settings = AppSettings::load();
+
             //blocking async init operations
-            let app_handle = app.handle().clone();
-            let (db_state, sip_state) = tauri::async_runtime::block_on(async move {
+            let startup_clock = clock.clone();
+            let (repo, snapshot) = tauri::async_runtime::block_on(async move {
                 let database = db::Database::new()
                     .await
                     .expect("failed to initialize database");
 
-                // Clone the pool before moving it
-                let cloned_pool = database.pool.clone();
+                let repo = SqliteRepository::new(database.pool);
 
-                let sip_state = SipState::default().read_from_db(&cloned_pool).await;
+                // Keep the goal used by the daily totals triggers in line with the settings file
+                if let Err(e) = repo
+                    .set_daily_goal(settings.daily_goal_ml, startup_clock.local_now().date_naive())
+                    .await
+                {
+                    eprintln!("Failed to store daily goal: {}", e);
+                }
+
+                let sip_state = SipState::default()
+                    .read_from_db(&repo, startup_clock.as_ref())
+                    .await;
+
+                // Sessions still open now were left behind by a crash
+                match repo.recover_sessions().await {
+                    Ok(0) => {}
+                    Ok(recovered) => {
+                        println!("Closed {} sessions left open by a crash", recovered)
+                    }
+                    Err(e) => eprintln!("Failed to recover sessions: {}", e),
+                }
 
-                let app_state = app_handle.state::<AppTimerState>();
-                let mut app_state = app_state.lock().ignore_poisoned();
+                let mut app_state = AppState::new(startup_clock.as_ref());
 
                 // Session creation is critical - crash if it fails
                 app_state
-                    .init_session(&cloned_pool)
+                    .init_session(&repo)
                     .await
                     .expect("Critical error: Failed to add session to database. App cannot function without session tracking.");
 
-                (db::DatabaseState(database.pool), sip_state)
+                // Pick up where the timer was left before a restart or update
+                if let Err(e) = app_state
+                    .restore_timer(&repo, &settings, startup_clock.as_ref())
+                    .await
+                {
+                    eprintln!("Failed to restore timer: {}", e);
+                }
+
+                (
+                    repo,
+                    StateSnapshot {
+                        settings,
+                        app_state,
+                        sip_state,
+                    },
+                )
             });
 
-            // Store database pool in app state
-            app.manage(db_state);
-            app.manage(SipTrackingState::new(sip_state));
+            // Reminders pause while the session is locked or idle
+            let (presence_tx, presence_rx) = watch::channel(Presence::default());
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = watch_presence(presence_tx).await {
+                    eprintln!("Stopped watching for idle and lock: {}", e);
+                }
+            });
 
-            let db_state = app.state::<DatabaseState>();
+            // Settings, timer and totals are owned by the actor, everything
+            // else talks to it through the handle
+            let (actor, handle) =
+                StateActor::new(snapshot, repo.clone(), clock.clone(), presence_rx);
+            tauri::async_runtime::spawn(actor.run());
 
-            let cloned_pool = db_state.0.clone();
+            app.manage(DatabaseState(repo));
+            app.manage(StateService(handle));
 
             // Clone the app handle so it can be moved into the spawned task
             let app_handle = app.handle().clone();
@@ -225,45 +224,88 @@ pub fn run() {
                 let mut interval = tokio::time::interval(Duration::from_secs(1));
 
                 loop {
-                    let timer_started = {
-                        let app_state = app_handle.state::<AppTimerState>();
-                        let app_state = app_state.lock().unwrap();
-                        app_state.timer_started
+                    interval.tick().await;
+
+                    let service = app_handle.state::<StateService>();
+                    let tick = match service.0.tick().await {
+                        Ok(tick) => tick,
+                        Err(e) => {
+                            eprintln!("Failed to check reminder: {}", e);
+                            continue;
+                        }
                     };
 
-                    interval.tick().await;
+                    if tick.timer_started {
+                        println!("Working hours began, started the timer");
+                    }
+                    if tick.snooze_ended {
+                        println!("Snooze ended");
+                    }
+                    if tick.timer_started || tick.snooze_ended {
+                        let app_state = service.0.snapshot().app_state;
+                        if let Err(e) = timer::timer_changed(&app_handle, &app_state) {
+                            eprintln!("Failed to update app_state: {}", e);
+                        }
+                    }
 
-                    let sip_state = app_handle.state::<SipTrackingState>();
-                    let mut locked_sip_state = sip_state.lock().await;
+                    if tick.new_day {
+                        // Yesterday may have ended the current streak
+                        if let Err(e) = refresh_streaks(&app_handle).await {
+                            eprintln!("Failed to refresh streaks: {}", e);
+                        }
 
-                    if !timer_started {
-                        continue;
+                        publish(
+                            &app_handle,
+                            DomainEvent::TotalsChanged(service.0.snapshot().sip_state),
+                        );
+                    }
+
+                    match tick.reminder() {
+                        Some(MessageId::WelcomeBack) => {
+                            println!("Sip is due, welcoming the user back")
+                        }
+                        Some(_) => println!("Sip is due"),
+                        None => continue,
                     }
 
-                    if locked_sip_state.check_if_sip_is_due() {
-                        println!("Sip is due");
-                        println!("notified_user {:#?}", locked_sip_state.notified_user);
-                        if !locked_sip_state.notified_user {
-                            match notify_sip(&app_handle) {
-                                Ok(_) => {
-                                    locked_sip_state.set_notified_user(true, &cloned_pool).await;
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to notify user: {}", e);
-                                }
+                    match notify_reminder(&app_handle, &tick).await {
+                        Ok(_) => {
+                            let sip_state = service.0.snapshot().sip_state;
+                            publish(
+                                &app_handle,
+                                DomainEvent::ReminderFired(ReminderFired {
+                                    fired_at: clock.now(),
+                                    last_sip_at: sip_state.last_sip_at(),
+                                }),
+                            );
+
+                            if let Err(e) = service.0.mark_notified().await {
+                                eprintln!("Failed to store notification: {}", e);
+                            } else if let Some(sip_id) = sip_state.last_sip_id {
+                                let db_state = app_handle.state::<DatabaseState>();
+                                publish_sip(&app_handle, &db_state, sip_id, DomainEvent::SipUpdated)
+                                    .await;
                             }
                         }
-                    } else {
-                        println!("Sip is not due");
-                        println!("notified_user {:#?}", locked_sip_state.notified_user);
+                        Err(e) => {
+                            eprintln!("Failed to notify user: {}", e);
+                        }
                     }
                 }
             });
 
+            tauri::async_runtime::spawn(run_heartbeat(app.handle().clone()));
+
             let app_handle = app.handle().clone();
 
             create_tray(&app_handle)?;
 
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = refresh_streaks(&app_handle).await {
+                    eprintln!("Failed to refresh streaks: {}", e);
+                }
+            });
+
             Ok(())
         })
         .build(tauri::generate_context!())
@@ -285,6 +327,12 @@ pub fn run() {
                 api.prevent_exit();
             }
         }
+        RunEvent::Exit => {
+            let ended = end_session(_app_handle, SessionEndReason::Quit);
+            if let Err(e) = tauri::async_runtime::block_on(ended) {
+                eprintln!("Failed to end session: {}", e);
+            }
+        }
         RunEvent::WindowEvent {
             event: tauri::WindowEvent::CloseRequested { api, .. },
             label,
@@ -305,13 +353,3 @@ pub fn run() {
 }
 
 //https://github.com/tauri-apps/tauri/blob/dev/examples/api/src-tauri/src/tray.rs
-
-pub trait IgnorePoisoned<T> {
-    fn ignore_poisoned(self) -> T;
-}
-
-impl<T> IgnorePoisoned<T> for Result<T, PoisonError<T>> {
-    fn ignore_poisoned(self) -> T {
-        self.expect("poisoned")
-    }
-}
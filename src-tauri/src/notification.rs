@@ -1,12 +1,242 @@
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_notification::NotificationExt;
+#[cfg(all(unix, not(target_os = "macos")))]
+use waterer_core::reminder::ReminderAction;
+use waterer_core::{
+    achievement::AchievementRule,
+    audio::AudioAlerts,
+    goal::GoalProgress,
+    message::{Catalog, MessageContext, MessageId, Messages},
+    notifier::{
+        CommandNotifier, Notification, NotificationChannel, Notifier, Notifiers, SoundNotifier,
+    },
+    settings::AppSettings,
+    state_actor::Tick,
+    streak::streaks,
+};
 
-pub fn notify_sip(app: &AppHandle) -> anyhow::Result<()> {
-    app.notification()
-        .builder()
-        .title("Sip is due")
-        .body("Take a sip")
-        .show()?;
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::reminder::handle_action;
+use crate::{
+    audio::audio_alerts,
+    state::{ClockState, DatabaseState, StateService},
+};
+
+/// Renders message `id` in the user's language, with `fill` adding the values
+/// only this message has, and sends it down the channels configured for its
+/// kind. `step` counts the reminders since the latest sip and picks the sound.
+/// Returns the channel that delivered it, `None` if notifications are turned
+/// off or the kind is silenced.
+async fn deliver(
+    app: &AppHandle,
+    id: MessageId,
+    step: u32,
+    fill: impl FnOnce(&mut MessageContext),
+) -> anyhow::Result<Option<NotificationChannel>> {
+    let snapshot = app.state::<StateService>().0.snapshot();
+    let settings = snapshot.settings;
+
+    let db_state = app.state::<DatabaseState>();
+    let clock = app.state::<ClockState>();
+
+    let streak_days = match streaks(
+        &db_state.0,
+        clock.0.as_ref(),
+        settings.streak_tolerance_percent,
+    )
+    .await
+    {
+        Ok(streaks) => streaks.current_days,
+        Err(e) => {
+            eprintln!("Failed to compute streaks for a notification: {}", e);
+            0
+        }
+    };
+
+    let mut context = MessageContext::new(
+        &settings,
+        &snapshot.sip_state,
+        streak_days,
+        clock.0.local_now(),
+    );
+    fill(&mut context);
+    let notification = messages(&settings).render(id, &context).with_step(step);
+    let alerts = audio_alerts(app);
+
+    notifiers(app, &settings, &alerts).deliver(&notification, &settings, &alerts)
+}
+
+/// Every channel this app can deliver over, as set up in `settings`
+fn notifiers(app: &AppHandle, settings: &AppSettings, alerts: &AudioAlerts) -> Notifiers {
+    let notifiers = Notifiers::new()
+        .with(ToastNotifier {
+            app: app.clone(),
+            actionable: settings.actionable_notifications,
+        })
+        .with(OverlayNotifier { app: app.clone() })
+        .with(SoundNotifier::new(alerts.clone()));
+
+    match CommandNotifier::from_command(&settings.notification_command) {
+        Some(command) => notifiers.with(command),
+        None => notifiers,
+    }
+}
+
+/// The catalog of the chosen language, or of the system's, with the user's
+/// templates on top
+fn messages(settings: &AppSettings) -> Messages {
+    let locale = settings
+        .locale
+        .clone()
+        .or_else(tauri_plugin_os::locale)
+        .unwrap_or_default();
+
+    Messages::new(
+        Catalog::for_locale(&locale),
+        settings.message_templates.clone(),
+    )
+}
+
+/// Shows the reminder `tick` calls for, if any
+pub async fn notify_reminder(
+    app: &AppHandle,
+    tick: &Tick,
+) -> anyhow::Result<Option<NotificationChannel>> {
+    let Some(id) = tick.reminder() else {
+        return Ok(None);
+    };
+
+    // One reminder covers the time away instead of every one that was held
+    deliver(app, id, tick.reminder_step, |context| {
+        if let Some(away_for) = tick.welcome_back {
+            context.away_minutes = away_for.num_minutes();
+        }
+    })
+    .await
+}
+
+pub async fn notify_achievement(app: &AppHandle, rule: &AchievementRule) -> anyhow::Result<()> {
+    deliver(app, MessageId::Achievement, 1, |context| {
+        context.achievement = rule.title.clone();
+        context.description = rule.description.clone();
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Announces the daily goal or one of the milestones towards it
+pub async fn notify_goal(app: &AppHandle, progress: &GoalProgress) -> anyhow::Result<()> {
+    deliver(app, progress.message(), 1, |context| {
+        context.total_ml = progress.total_ml;
+        context.goal_ml = progress.goal_ml;
+        // The goal itself keeps today's percentage, which may be past 100
+        if progress.message() == MessageId::GoalMilestone {
+            context.percent = i64::from(progress.percent);
+        }
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// The desktop notification. Reminders get buttons to log a sip, log a glass
+/// or snooze where the platform has notification actions.
+struct ToastNotifier {
+    app: AppHandle,
+    actionable: bool,
+}
+
+impl Notifier for ToastNotifier {
+    fn channel(&self) -> NotificationChannel {
+        NotificationChannel::Toast
+    }
+
+    fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if self.actionable && notification.kind.is_reminder() && supports_actions() {
+            match show_actionable(&self.app, &notification.title, &notification.body) {
+                Ok(()) => return Ok(()),
+                Err(e) => eprintln!("Failed to show reminder with actions: {}", e),
+            }
+        }
+
+        self.app
+            .notification()
+            .builder()
+            .title(&notification.title)
+            .body(&notification.body)
+            .show()?;
+
+        Ok(())
+    }
+}
+
+/// A banner in the main window, only shown while the window is visible
+struct OverlayNotifier {
+    app: AppHandle,
+}
+
+impl Notifier for OverlayNotifier {
+    fn channel(&self) -> NotificationChannel {
+        NotificationChannel::Overlay
+    }
+
+    fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        let window = self
+            .app
+            .get_webview_window("main")
+            .ok_or_else(|| anyhow::anyhow!("The main window is not open"))?;
+        if !window.is_visible()? {
+            return Err(anyhow::anyhow!("The main window is hidden"));
+        }
+
+        self.app.emit_to("main", "show-overlay", notification)?;
+
+        Ok(())
+    }
+}
+
+/// Whether the notification server can show buttons
+#[cfg(all(unix, not(target_os = "macos")))]
+fn supports_actions() -> bool {
+    match notify_rust::get_capabilities() {
+        Ok(capabilities) => capabilities
+            .iter()
+            .any(|capability| capability == "actions"),
+        Err(e) => {
+            eprintln!("Failed to read notification capabilities: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show_actionable(app: &AppHandle, title: &str, body: &str) -> anyhow::Result<()> {
+    let fired_at = app.state::<ClockState>().0.now();
+
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname(&app.package_info().name)
+        .summary(title)
+        .body(body);
+    for action in ReminderAction::BUTTONS {
+        notification.action(action.as_str(), &action.label());
+    }
+    let handle = notification.show()?;
+
+    // Waiting for the click blocks, so it gets a thread of its own
+    let app = app.clone();
+    std::thread::spawn(move || {
+        handle.wait_for_action(|id| match ReminderAction::from_id(id) {
+            Some(action) => {
+                tauri::async_runtime::spawn(async move {
+                    handle_action(&app, action, fired_at).await;
+                });
+            }
+            None => println!("reminder action {} not handled", id),
+        });
+    });
 
     Ok(())
 }
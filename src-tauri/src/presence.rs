@@ -0,0 +1,387 @@
+use tokio::sync::watch;
+use waterer_core::presence::{NoPresenceMonitor, Presence, PresenceMonitor};
+
+/// Reports session idle and lock changes to `presence` for as long as the
+/// app runs. Without a way to detect them the user always counts as present.
+pub async fn watch_presence(presence: watch::Sender<Presence>) -> anyhow::Result<()> {
+    #[cfg(target_os = "linux")]
+    match dbus::DbusPresenceMonitor::connect().await {
+        Ok(monitor) => return monitor.run(presence).await,
+        Err(e) => eprintln!(
+            "Failed to connect to D-Bus, reminders won't pause while away: {}",
+            e
+        ),
+    }
+
+    NoPresenceMonitor.run(presence).await
+}
+
+/// Idle and lock detection through logind and the freedesktop ScreenSaver
+/// interface
+#[cfg(target_os = "linux")]
+pub mod dbus {
+    use chrono::{DateTime, Utc};
+    use futures_util::{
+        stream::{select_all, BoxStream},
+        StreamExt,
+    };
+    use tokio::sync::watch;
+    use waterer_core::presence::{Presence, PresenceMonitor};
+    use zbus::{proxy, Connection};
+
+    #[proxy(
+        interface = "org.freedesktop.login1.Session",
+        default_service = "org.freedesktop.login1",
+        default_path = "/org/freedesktop/login1/session/auto"
+    )]
+    trait LoginSession {
+        #[zbus(signal)]
+        fn lock(&self) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        fn unlock(&self) -> zbus::Result<()>;
+
+        #[zbus(property)]
+        fn locked_hint(&self) -> zbus::Result<bool>;
+
+        /// Set by the desktop once it considers the session idle
+        #[zbus(property)]
+        fn idle_hint(&self) -> zbus::Result<bool>;
+
+        /// When the session became idle, in microseconds since the epoch
+        #[zbus(property)]
+        fn idle_since_hint(&self) -> zbus::Result<u64>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.ScreenSaver",
+        default_service = "org.freedesktop.ScreenSaver",
+        default_path = "/org/freedesktop/ScreenSaver"
+    )]
+    trait ScreenSaver {
+        #[zbus(signal)]
+        fn active_changed(&self, active: bool) -> zbus::Result<()>;
+
+        fn get_active(&self) -> zbus::Result<bool>;
+    }
+
+    /// Watches the logind session on the system bus and, where the desktop
+    /// provides it, the screensaver on the session bus
+    pub struct DbusPresenceMonitor {
+        system: Connection,
+        session: Option<Connection>,
+    }
+
+    impl DbusPresenceMonitor {
+        pub async fn connect() -> anyhow::Result<Self> {
+            let system = Connection::system().await?;
+            let session = match Connection::session().await {
+                Ok(session) => Some(session),
+                Err(e) => {
+                    eprintln!("Failed to connect to the session bus: {}", e);
+                    None
+                }
+            };
+
+            Ok(Self::with_connections(system, session))
+        }
+
+        /// Uses the given buses, e.g. a private bus serving mock logind and
+        /// screensaver objects
+        pub fn with_connections(system: Connection, session: Option<Connection>) -> Self {
+            Self { system, session }
+        }
+    }
+
+    impl PresenceMonitor for DbusPresenceMonitor {
+        async fn run(self, presence: watch::Sender<Presence>) -> anyhow::Result<()> {
+            let login = LoginSessionProxy::new(&self.system).await?;
+
+            let mut changes: Vec<BoxStream<'static, ()>> = vec![
+                login.receive_lock().await?.map(|_| ()).boxed(),
+                login.receive_unlock().await?.map(|_| ()).boxed(),
+                login
+                    .receive_locked_hint_changed()
+                    .await
+                    .map(|_| ())
+                    .boxed(),
+                login.receive_idle_hint_changed().await.map(|_| ()).boxed(),
+            ];
+
+            // Not every desktop implements the screensaver interface
+            let screensaver = match &self.session {
+                Some(session) => ScreenSaverProxy::new(session).await.ok(),
+                None => None,
+            };
+            if let Some(screensaver) = &screensaver {
+                match screensaver.receive_active_changed().await {
+                    Ok(stream) => changes.push(stream.map(|_| ()).boxed()),
+                    Err(e) => eprintln!("Failed to watch the screensaver: {}", e),
+                }
+            }
+
+            let mut changes = select_all(changes);
+            loop {
+                let current = read_presence(&login, screensaver.as_ref()).await?;
+                presence.send_if_modified(|previous| {
+                    let modified = *previous != current;
+                    *previous = current;
+                    modified
+                });
+
+                if presence.is_closed() || changes.next().await.is_none() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Reads the current state instead of trusting the signal that woke us,
+    /// so a missed or reordered signal can't leave a stale state behind
+    async fn read_presence(
+        login: &LoginSessionProxy<'_>,
+        screensaver: Option<&ScreenSaverProxy<'_>>,
+    ) -> zbus::Result<Presence> {
+        let screensaver_active = match screensaver {
+            Some(screensaver) => screensaver.get_active().await.unwrap_or(false),
+            None => false,
+        };
+        if screensaver_active || login.locked_hint().await? {
+            return Ok(Presence::Locked);
+        }
+
+        if login.idle_hint().await? {
+            let since = DateTime::from_timestamp_micros(login.idle_since_hint().await? as i64)
+                .unwrap_or_else(Utc::now);
+            return Ok(Presence::Idle { since });
+        }
+
+        Ok(Presence::Active)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::{collections::HashMap, os::unix::net::UnixStream, time::Duration};
+
+        use chrono::{DateTime, Utc};
+        use serde::Serialize;
+        use tokio::sync::watch;
+        use waterer_core::presence::{Presence, PresenceMonitor};
+        use zbus::{
+            connection::Builder,
+            interface,
+            message::Message,
+            object_server::{Interface, InterfaceRef},
+            zvariant::{DynamicType, Value},
+            Connection, Guid,
+        };
+
+        use super::DbusPresenceMonitor;
+
+        const SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+        const SCREENSAVER_PATH: &str = "/org/freedesktop/ScreenSaver";
+        /// The unique name the mock services send their signals from
+        const SERVICE_NAME: &str = ":1.1";
+
+        /// Just enough of the bus for proxies to look up who owns their
+        /// service's name
+        struct MockBus;
+
+        #[interface(name = "org.freedesktop.DBus")]
+        impl MockBus {
+            fn get_name_owner(&self, _name: &str) -> String {
+                SERVICE_NAME.to_string()
+            }
+        }
+
+        #[derive(Default)]
+        struct MockSession {
+            locked: bool,
+            idle_since: Option<u64>,
+        }
+
+        #[interface(name = "org.freedesktop.login1.Session")]
+        impl MockSession {
+            #[zbus(property)]
+            fn locked_hint(&self) -> bool {
+                self.locked
+            }
+
+            #[zbus(property)]
+            fn idle_hint(&self) -> bool {
+                self.idle_since.is_some()
+            }
+
+            #[zbus(property)]
+            fn idle_since_hint(&self) -> u64 {
+                self.idle_since.unwrap_or_default()
+            }
+        }
+
+        #[derive(Default)]
+        struct MockScreenSaver {
+            active: bool,
+        }
+
+        #[interface(name = "org.freedesktop.ScreenSaver")]
+        impl MockScreenSaver {
+            fn get_active(&self) -> bool {
+                self.active
+            }
+        }
+
+        /// An interface served at `path` on one end of a peer to peer
+        /// connection within this process
+        struct Mock<I> {
+            iface: InterfaceRef<I>,
+            server: Connection,
+            path: &'static str,
+        }
+
+        impl<I: Interface> Mock<I> {
+            /// Returns the mock and the client's end of the connection
+            async fn serve(path: &'static str, iface: I) -> (Self, Connection) {
+                let (server, client) = UnixStream::pair().unwrap();
+                let server = Builder::unix_stream(server)
+                    .server(Guid::generate())
+                    .unwrap()
+                    .p2p()
+                    .serve_at("/org/freedesktop/DBus", MockBus)
+                    .unwrap()
+                    .serve_at(path, iface)
+                    .unwrap()
+                    .build();
+                let client = Builder::unix_stream(client).p2p().build();
+
+                let (server, client) = tokio::join!(server, client);
+                let server = server.unwrap();
+                let iface = server
+                    .object_server()
+                    .interface::<_, I>(path)
+                    .await
+                    .unwrap();
+                let mock = Self {
+                    iface,
+                    server,
+                    path,
+                };
+                (mock, client.unwrap())
+            }
+
+            /// Peer to peer connections don't stamp a sender on signals, and
+            /// proxies drop signals that aren't from their service's owner
+            async fn emit<B>(&self, interface: &str, member: &str, body: &B)
+            where
+                B: Serialize + DynamicType,
+            {
+                let signal = Message::signal(self.path, interface, member)
+                    .unwrap()
+                    .sender(SERVICE_NAME)
+                    .unwrap()
+                    .build(body)
+                    .unwrap();
+                self.server.send(&signal).await.unwrap();
+            }
+
+            async fn signal<B>(&self, member: &str, body: &B)
+            where
+                B: Serialize + DynamicType,
+            {
+                let name = I::name();
+                self.emit(name.as_str(), member, body).await;
+            }
+
+            async fn properties_changed(&self, changed: &[(&str, Value<'_>)]) {
+                let changed: HashMap<_, _> = changed.iter().cloned().collect();
+                let name = I::name();
+                let body = (name.as_str(), changed, Vec::<&str>::new());
+                self.emit(
+                    "org.freedesktop.DBus.Properties",
+                    "PropertiesChanged",
+                    &body,
+                )
+                .await;
+            }
+        }
+
+        impl Mock<MockSession> {
+            async fn set_locked(&self, locked: bool) {
+                self.iface.get_mut().await.locked = locked;
+                self.properties_changed(&[("LockedHint", Value::from(locked))])
+                    .await;
+                self.signal(if locked { "Lock" } else { "Unlock" }, &())
+                    .await;
+            }
+
+            async fn set_idle_since(&self, idle_since: Option<u64>) {
+                self.iface.get_mut().await.idle_since = idle_since;
+                self.properties_changed(&[
+                    ("IdleHint", Value::from(idle_since.is_some())),
+                    ("IdleSinceHint", Value::from(idle_since.unwrap_or_default())),
+                ])
+                .await;
+            }
+        }
+
+        impl Mock<MockScreenSaver> {
+            async fn set_active(&self, active: bool) {
+                self.iface.get_mut().await.active = active;
+                self.signal("ActiveChanged", &active).await;
+            }
+        }
+
+        /// Waits for the monitor to report `expected`. Changes on the two
+        /// connections may arrive in either order, so the states passed on
+        /// the way there aren't checked.
+        async fn reaches(presence: &mut watch::Receiver<Presence>, expected: Presence) {
+            tokio::time::timeout(
+                Duration::from_secs(5),
+                presence.wait_for(|current| *current == expected),
+            )
+            .await
+            .unwrap_or_else(|_| panic!("presence didn't become {:?} within 5 seconds", expected))
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn follows_the_session_and_the_screensaver() {
+            let (login, system) = Mock::serve(SESSION_PATH, MockSession::default()).await;
+            let (screensaver, session) =
+                Mock::serve(SCREENSAVER_PATH, MockScreenSaver::default()).await;
+
+            let (presence_tx, mut presence) = watch::channel(Presence::Active);
+            let monitor = DbusPresenceMonitor::with_connections(system, Some(session));
+            let monitor = tokio::spawn(monitor.run(presence_tx));
+
+            login.set_locked(true).await;
+            reaches(&mut presence, Presence::Locked).await;
+
+            login.set_locked(false).await;
+            reaches(&mut presence, Presence::Active).await;
+
+            let since: DateTime<Utc> = "2025-06-10T08:00:00Z".parse().unwrap();
+            login
+                .set_idle_since(Some(since.timestamp_micros() as u64))
+                .await;
+            reaches(&mut presence, Presence::Idle { since }).await;
+
+            // The screensaver counts as locked even while logind only
+            // reports the session idle
+            screensaver.set_active(true).await;
+            reaches(&mut presence, Presence::Locked).await;
+
+            login.set_idle_since(None).await;
+            screensaver.set_active(false).await;
+            reaches(&mut presence, Presence::Active).await;
+
+            // With nobody listening anymore the next change ends the monitor
+            drop(presence);
+            login.set_locked(true).await;
+            tokio::time::timeout(Duration::from_secs(5), monitor)
+                .await
+                .expect("the monitor kept running")
+                .unwrap()
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use tauri::{AppHandle, Manager, State};
+use waterer_core::{
+    app_state::AppState,
+    reminder::{ReminderAction, ReminderOutcome, ReminderOutcomeRepository, SNOOZE_MINUTES},
+    sip::SipSource,
+    state_actor::LoggedSip,
+};
+
+use crate::{
+    sip::{confirm_sip, log_sip},
+    state::{ClockState, DatabaseState, StateService},
+    timer::timer_changed,
+};
+
+/// Outcomes listed when no limit is given
+const DEFAULT_OUTCOME_LIMIT: u32 = 50;
+
+/// Holds reminders for `minutes`. Shared by the notification action and the
+/// command.
+pub async fn snooze(app: &AppHandle, minutes: i64) -> anyhow::Result<AppState> {
+    let app_state = app.state::<StateService>().0.snooze(minutes).await?;
+    timer_changed(app, &app_state)?;
+
+    Ok(app_state)
+}
+
+/// Runs the action picked on the reminder fired at `fired_at` and records it
+pub async fn handle_action(app: &AppHandle, action: ReminderAction, fired_at: DateTime<Utc>) {
+    println!("reminder action {} was clicked", action.as_str());
+
+    let sip_id = match action {
+        ReminderAction::LogSip | ReminderAction::LogGlass => {
+            let amount_ml = (action == ReminderAction::LogGlass).then(|| {
+                app.state::<StateService>()
+                    .0
+                    .snapshot()
+                    .settings
+                    .glass_amount_ml
+            });
+
+            match log_sip(app, amount_ml, SipSource::Notification, None, false).await {
+                Ok(LoggedSip::Logged { sip_id, .. }) => sip_id,
+                Ok(LoggedSip::AlreadyLogged(_)) => None,
+                Ok(LoggedSip::NeedsConfirmation(check)) => {
+                    confirm_sip(app, &check, amount_ml, SipSource::Notification);
+                    None
+                }
+                Err(e) => {
+                    eprintln!("Failed to take sip: {}", e);
+                    None
+                }
+            }
+        }
+        ReminderAction::Snooze => {
+            if let Err(e) = snooze(app, SNOOZE_MINUTES).await {
+                eprintln!("Failed to snooze reminders: {}", e);
+            }
+            None
+        }
+        ReminderAction::Dismissed => None,
+    };
+
+    let db_state = app.state::<DatabaseState>();
+    let clock = app.state::<ClockState>();
+    if let Err(e) = db_state
+        .0
+        .record_reminder_outcome(fired_at, action, clock.0.now(), sip_id)
+        .await
+    {
+        eprintln!("Failed to record reminder outcome: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn snooze_reminder(app: AppHandle, minutes: Option<i64>) -> Result<AppState, String> {
+    let minutes = minutes.unwrap_or(SNOOZE_MINUTES);
+    if minutes <= 0 {
+        return Err("Snooze minutes must be greater than 0".to_string());
+    }
+
+    snooze(&app, minutes)
+        .await
+        .map_err(|e| format!("Failed to snooze reminders: {}", e))
+}
+
+/// What was done with the latest reminders, newest first
+#[tauri::command]
+pub async fn get_reminder_outcomes(
+    db_state: State<'_, DatabaseState>,
+    limit: Option<u32>,
+) -> Result<Vec<ReminderOutcome>, String> {
+    db_state
+        .0
+        .reminder_outcomes(limit.unwrap_or(DEFAULT_OUTCOME_LIMIT))
+        .await
+        .map_err(|e| format!("Failed to fetch reminder outcomes: {}", e))
+}
@@ -0,0 +1,22 @@
+use tauri::State;
+use waterer_core::schedule::{ReminderPolicy, SchedulePreview};
+
+use crate::state::{ClockState, StateService};
+
+#[tauri::command]
+pub async fn preview_schedule(
+    service: State<'_, StateService>,
+    clock: State<'_, ClockState>,
+) -> Result<SchedulePreview, String> {
+    let snapshot = service.0.snapshot();
+    let policy = ReminderPolicy::from_settings(&snapshot.settings);
+
+    Ok(policy.preview(
+        snapshot
+            .sip_state
+            .last_sip_at()
+            .map(|at| at.with_timezone(&clock.0.zone())),
+        snapshot.sip_state.total_amount_today,
+        clock.0.local_now(),
+    ))
+}
@@ -0,0 +1,98 @@
+use tauri::{AppHandle, Manager, State};
+use tokio::time::Duration;
+use waterer_core::{
+    repository::SessionRepository,
+    session::{SessionEndReason, SessionSummary, TimerRunSummary, HEARTBEAT_INTERVAL_SECONDS},
+};
+
+use crate::state::{ClockState, DatabaseState, StateService};
+
+/// Keeps the current session's heartbeat fresh so a crash can be dated
+pub async fn run_heartbeat(app: AppHandle) {
+    let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECONDS));
+
+    loop {
+        interval.tick().await;
+
+        let Some(session_id) = app
+            .state::<StateService>()
+            .0
+            .snapshot()
+            .app_state
+            .session_id
+        else {
+            continue;
+        };
+
+        let db_state = app.state::<DatabaseState>();
+        let clock = app.state::<ClockState>();
+        if let Err(e) = db_state
+            .0
+            .session_heartbeat(session_id, clock.0.now())
+            .await
+        {
+            eprintln!("Failed to store session heartbeat: {}", e);
+        }
+    }
+}
+
+/// Closes the current session when the app quits or restarts
+pub async fn end_session(app: &AppHandle, reason: SessionEndReason) -> anyhow::Result<()> {
+    let Some(session_id) = app
+        .state::<StateService>()
+        .0
+        .snapshot()
+        .app_state
+        .session_id
+    else {
+        return Ok(());
+    };
+
+    let db_state = app.state::<DatabaseState>();
+    let clock = app.state::<ClockState>();
+    db_state
+        .0
+        .end_session(session_id, clock.0.now(), reason)
+        .await?;
+
+    Ok(())
+}
+
+/// Every session with its duration, sip count and volume, newest first
+#[tauri::command]
+pub async fn get_sessions(
+    db_state: State<'_, DatabaseState>,
+    clock: State<'_, ClockState>,
+) -> Result<Vec<SessionSummary>, String> {
+    let now = clock.0.now();
+
+    let sessions = db_state
+        .0
+        .session_summaries()
+        .await
+        .map_err(|e| format!("Failed to fetch sessions: {}", e))?;
+
+    Ok(sessions
+        .into_iter()
+        .map(|session| session.with_duration(now))
+        .collect())
+}
+
+/// Timer runs with their duration, sip count and volume, newest first,
+/// limited to one session if given
+#[tauri::command]
+pub async fn get_timer_runs(
+    db_state: State<'_, DatabaseState>,
+    clock: State<'_, ClockState>,
+    session_id: Option<i64>,
+) -> Result<Vec<TimerRunSummary>, String> {
+    let now = clock.0.now();
+
+    let runs = db_state
+        .0
+        .timer_run_summaries(session_id)
+        .await
+        .map_err(|e| format!("Failed to fetch timer runs: {}", e))?;
+
+    Ok(runs.into_iter().map(|run| run.with_duration(now)).collect())
+}
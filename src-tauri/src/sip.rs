@@ -1,141 +1,171 @@
-use crate::db::DatabaseState;
-use chrono::{self, DateTime, Local, NaiveTime, Utc};
-use serde::Serialize;
-use sqlx::{prelude::FromRow, Pool, Sqlite};
-
-#[derive(Debug, FromRow, Serialize)]
-pub struct Sip {
-    pub id: i64,
-    pub amount: i64,
-    pub created_at: DateTime<Utc>,
-    pub notified_user: bool,
-}
-
-#[derive(Debug, Default, Clone, Serialize)]
-pub struct SipState {
-    last_sip_timestamp: i64,
-    total_sips_today: i64,
-    pub total_amount_today: i64,
-    total_sips_all_time: i64,
-    total_amount_all_time: i64,
-    pub last_sip_id: Option<i64>,
-    pub notified_user: bool,
-}
-
-const SIP_INTERVAL_SECONDS: i64 = 10;
-
-impl SipState {
-    pub async fn read_from_db(&self, pool: &Pool<Sqlite>) -> Self {
-        let sips = match sqlx::query_as::<_, Sip>("SELECT * FROM sips ORDER BY created_at DESC")
-            .fetch_all(pool)
-            .await
-        {
-            Ok(sips) => sips,
-            Err(e) => {
-                eprintln!("Failed to fetch sips from database: {}", e);
-                return Self::default();
-            }
-        };
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use waterer_core::{
+    events::DomainEvent,
+    intake::IntakeCheck,
+    sip::{Sip, SipSource, SipState},
+    sip_query::{SipPage, SipQuery, SipQueryRepository, DEFAULT_PAGE_SIZE},
+    state_actor::LoggedSip,
+};
+
+use crate::{
+    achievement::check_achievements,
+    events::{publish, publish_sip},
+    goal::check_goal,
+    state::{DatabaseState, StateService},
+    streak::refresh_streaks,
+};
+
+/// Logs a sip of `amount_ml`, or of the configured amount if not given.
+/// Every entry point goes through here so the tray, the notifications and the
+/// commands behave the same. Unless `confirmed` is set, sips over an intake
+/// limit or right after the previous one are handed back for confirmation.
+/// A `request_id` that was already stored is not logged again.
+pub async fn log_sip(
+    app: &AppHandle,
+    amount_ml: Option<i64>,
+    source: SipSource,
+    request_id: Option<&str>,
+    confirmed: bool,
+) -> anyhow::Result<LoggedSip> {
+    let service = app.state::<StateService>();
+    let logged = service
+        .0
+        .log_sip(amount_ml, source, request_id.map(str::to_string), confirmed)
+        .await?;
 
-        if sips.is_empty() {
-            return Self::default();
+    if let LoggedSip::Logged {
+        sip_state,
+        sip_id,
+        merged,
+    } = &logged
+    {
+        if let Some(sip_id) = sip_id {
+            let event = if *merged {
+                DomainEvent::SipUpdated
+            } else {
+                DomainEvent::SipLogged
+            };
+            let db_state = app.state::<DatabaseState>();
+            publish_sip(app, &db_state, *sip_id, event).await;
         }
+        publish(app, DomainEvent::TotalsChanged(sip_state.clone()));
 
-        let last_sip = sips.first().unwrap(); // Safe because we checked is_empty() above
-
-        let last_sip_timestamp_parsed = last_sip.created_at;
+        on_sip_logged(app, sip_state).await;
+    }
 
-        let mut total_amount_all_time = 0;
-        let mut total_amount_today = 0;
-        let mut total_sips_all_time = 0;
-        let mut total_sips_today = 0;
+    Ok(logged)
+}
 
-        let local_now = Local::now();
-        let start_of_today = local_now
-            .with_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-            .unwrap();
+/// Asks before logging a sip that goes over an intake limit or follows the
+/// previous one too closely
+pub fn confirm_sip(
+    app: &AppHandle,
+    check: &IntakeCheck,
+    amount_ml: Option<i64>,
+    source: SipSource,
+) {
+    let Some(warning) = check.warning() else {
+        return;
+    };
+
+    let app = app.clone();
+    app.dialog()
+        .message(warning)
+        .title("Log another sip?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Log anyway".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            if !confirmed {
+                return;
+            }
 
-        for sip in &sips {
-            total_amount_all_time += sip.amount;
-            total_sips_all_time += 1;
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = log_sip(&app, amount_ml, source, None, true).await {
+                    eprintln!("Failed to take sip: {}", e);
+                }
+            });
+        });
+}
 
-            let sip_local = sip.created_at.with_timezone(&Local);
-            if sip_local >= start_of_today {
-                total_amount_today += sip.amount;
-                total_sips_today += 1;
-            }
+/// Follow-up work shared by every path that logs a sip
+pub async fn on_sip_logged(app: &AppHandle, sip_state: &SipState) {
+    let streaks = match refresh_streaks(app).await {
+        Ok(streaks) => Some(streaks),
+        Err(e) => {
+            eprintln!("Failed to refresh streaks: {}", e);
+            None
         }
+    };
 
-        Self {
-            last_sip_timestamp: last_sip_timestamp_parsed.timestamp(),
-            total_amount_all_time,
-            total_amount_today,
-            total_sips_all_time,
-            total_sips_today,
-            notified_user: last_sip.notified_user,
-            last_sip_id: Some(last_sip.id),
-        }
+    if let Err(e) = check_goal(app, sip_state).await {
+        eprintln!("Failed to check daily goal: {}", e);
     }
 
-    pub async fn take_sip(
-        &self,
-        amount: i64,
-        pool: &Pool<Sqlite>,
-        session_id: i64,
-    ) -> anyhow::Result<Self> {
-        sqlx::query!(
-            "INSERT INTO sips (amount, session_id) VALUES (?, ?)",
-            amount,
-            session_id
-        )
-        .execute(pool)
-        .await?;
-
-        // Instead of re-reading from DB, we can optimize by updating the state directly
-        Ok(self.read_from_db(pool).await)
+    if let Err(e) = check_achievements(app, sip_state, streaks.as_ref()).await {
+        eprintln!("Failed to check achievements: {}", e);
     }
+}
 
-    pub fn check_if_sip_is_due(&self) -> bool {
-        let now = Local::now();
-        let last_sip_local = match DateTime::from_timestamp(self.last_sip_timestamp, 0) {
-            Some(date_time) => date_time,
-            // If the last sip timestamp is not set, return true
-            None => return true,
-        };
-        let diff = now.signed_duration_since(last_sip_local);
-        diff.num_seconds() > SIP_INTERVAL_SECONDS
-    }
+#[tauri::command]
+pub async fn get_sips(db_state: tauri::State<'_, DatabaseState>) -> Result<Vec<Sip>, String> {
+    let repo = &db_state.0;
 
-    pub async fn set_notified_user(
-        &mut self,
-        notified_user: bool,
-        pool: &Pool<Sqlite>,
-    ) -> anyhow::Result<()> {
-        let Some(last_sip_id) = self.last_sip_id else {
-            return Ok(());
-        };
-
-        sqlx::query!(
-            "UPDATE sips SET notified_user = ? WHERE id = ?",
-            notified_user,
-            last_sip_id
-        )
-        .execute(pool)
-        .await?;
+    repo.all_sips()
+        .await
+        .map_err(|e| format!("Failed to fetch sips: {}", e))
+}
 
-        // Update the local state
-        self.notified_user = notified_user;
+/// A page of the sip history matching the filters, newest first
+#[tauri::command]
+pub async fn query_sips(
+    db_state: tauri::State<'_, DatabaseState>,
+    query: Option<SipQuery>,
+) -> Result<SipPage, String> {
+    let query = query.unwrap_or_default();
+    query.validate().map_err(|e| e.to_string())?;
+
+    db_state
+        .0
+        .query_sips(&query)
+        .await
+        .map_err(|e| format!("Failed to fetch sips: {}", e))
+}
 
-        Ok(())
-    }
+/// The latest sips for the main list
+#[tauri::command]
+pub async fn get_recent_sips(
+    db_state: tauri::State<'_, DatabaseState>,
+    limit: Option<u32>,
+) -> Result<Vec<Sip>, String> {
+    db_state
+        .0
+        .recent_sips(limit.unwrap_or(DEFAULT_PAGE_SIZE))
+        .await
+        .map_err(|e| format!("Failed to fetch sips: {}", e))
 }
 
+/// Today's and all-time totals without loading any sips
 #[tauri::command]
-pub async fn get_sips(db_state: tauri::State<'_, DatabaseState>) -> Result<Vec<Sip>, String> {
-    let pool = &db_state.0;
+pub async fn get_sip_state(service: tauri::State<'_, StateService>) -> Result<SipState, String> {
+    Ok(service.0.snapshot().sip_state)
+}
 
-    sqlx::query_as::<_, Sip>("SELECT * FROM sips ORDER BY created_at DESC")
-        .fetch_all(pool)
+/// The warning to confirm before logging a sip of `amount`, `None` if it can
+/// be logged right away
+#[tauri::command]
+pub async fn check_sip(
+    service: tauri::State<'_, StateService>,
+    amount: Option<i64>,
+) -> Result<Option<String>, String> {
+    let check = service
+        .0
+        .check_sip(amount)
         .await
-        .map_err(|e| format!("Failed to fetch sips: {}", e))
+        .map_err(|e| format!("Failed to check intake limits: {}", e))?;
+
+    Ok(check.warning())
 }
@@ -1,50 +1,18 @@
 // src-tauri/src/state.rs
-use crate::db::DatabaseState;
-use std::sync::{Mutex as SyncMutex, MutexGuard, PoisonError};
-use tokio::sync::Mutex;
+use std::sync::Arc;
+use waterer_core::{
+    audio::AudioSink, clock::Clock, repository::SqliteRepository, state_actor::StateHandle,
+};
 
-use crate::{AppSettings, AppState, SipState};
+/// Wrapper for the database repository
+pub struct DatabaseState(pub SqliteRepository);
 
-/// Wrapper for application settings state
-pub struct SettingsState(SyncMutex<AppSettings>);
+/// Wrapper for the clock shared by commands and background tasks
+pub struct ClockState(pub Arc<dyn Clock>);
 
-impl SettingsState {
-    pub fn new(settings: AppSettings) -> Self {
-        Self(SyncMutex::new(settings))
-    }
+/// Wrapper for the output notification sounds are played on
+pub struct AudioState(pub Arc<dyn AudioSink>);
 
-    pub fn lock(
-        &self,
-    ) -> Result<MutexGuard<'_, AppSettings>, PoisonError<std::sync::MutexGuard<'_, AppSettings>>>
-    {
-        self.0.lock()
-    }
-}
-
-/// Wrapper for sip tracking state
-pub struct SipTrackingState(Mutex<SipState>);
-
-impl SipTrackingState {
-    pub fn new(sip_state: SipState) -> Self {
-        Self(Mutex::new(sip_state))
-    }
-
-    pub async fn lock(&self) -> tokio::sync::MutexGuard<'_, SipState> {
-        self.0.lock().await
-    }
-}
-
-/// Wrapper for app timer state
-pub struct AppTimerState(SyncMutex<AppState>);
-
-impl AppTimerState {
-    pub fn new(app_state: AppState) -> Self {
-        Self(SyncMutex::new(app_state))
-    }
-
-    pub fn lock(
-        &self,
-    ) -> Result<MutexGuard<'_, AppState>, PoisonError<std::sync::MutexGuard<'_, AppState>>> {
-        self.0.lock()
-    }
-}
+/// Wrapper for the handle to the actor that owns the settings, the timer and
+/// the sip totals
+pub struct StateService(pub StateHandle);
@@ -0,0 +1,27 @@
+use chrono::NaiveDate;
+use tauri::State;
+use waterer_core::stats::{heatmap, statistics, Heatmap, Statistics};
+
+use crate::state::DatabaseState;
+
+#[tauri::command]
+pub async fn get_statistics(
+    db_state: State<'_, DatabaseState>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Statistics, String> {
+    statistics(&db_state.0, from, to)
+        .await
+        .map_err(|e| format!("Failed to compute statistics: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_heatmap(
+    db_state: State<'_, DatabaseState>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Heatmap, String> {
+    heatmap(&db_state.0, from, to)
+        .await
+        .map_err(|e| format!("Failed to compute heatmap: {}", e))
+}
@@ -0,0 +1,63 @@
+use chrono::NaiveDate;
+use tauri::{AppHandle, Manager, State};
+use waterer_core::streak::{streaks, Streaks, VacationRepository};
+
+use crate::{
+    state::{ClockState, DatabaseState, StateService},
+    tray::update_streak_tooltip,
+};
+
+/// Recomputes the streaks and shows them in the tray tooltip
+pub async fn refresh_streaks(app: &AppHandle) -> anyhow::Result<Streaks> {
+    let tolerance_percent = app
+        .state::<StateService>()
+        .0
+        .snapshot()
+        .settings
+        .streak_tolerance_percent;
+
+    let db_state = app.state::<DatabaseState>();
+    let clock = app.state::<ClockState>();
+
+    let streaks = streaks(&db_state.0, clock.0.as_ref(), tolerance_percent).await?;
+    update_streak_tooltip(app, &streaks)?;
+
+    Ok(streaks)
+}
+
+#[tauri::command]
+pub async fn get_streaks(app: AppHandle) -> Result<Streaks, String> {
+    refresh_streaks(&app)
+        .await
+        .map_err(|e| format!("Failed to compute streaks: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_vacation_days(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<NaiveDate>, String> {
+    db_state
+        .0
+        .vacation_days()
+        .await
+        .map_err(|e| format!("Failed to fetch vacation days: {}", e))
+}
+
+#[tauri::command]
+pub async fn set_vacation_day(
+    app: AppHandle,
+    db_state: State<'_, DatabaseState>,
+    date: NaiveDate,
+    vacation: bool,
+) -> Result<Streaks, String> {
+    let result = if vacation {
+        db_state.0.add_vacation_day(date).await
+    } else {
+        db_state.0.remove_vacation_day(date).await
+    };
+    result.map_err(|e| format!("Failed to update vacation day: {}", e))?;
+
+    refresh_streaks(&app)
+        .await
+        .map_err(|e| format!("Failed to compute streaks: {}", e))
+}
@@ -0,0 +1,24 @@
+use tauri::{AppHandle, Emitter, Manager};
+use waterer_core::app_state::AppState;
+
+use crate::{state::StateService, tray::update_timer_menu_item};
+
+/// Starts or stops the reminder timer and tells the tray and the windows.
+/// Shared by the command and the tray menu.
+pub async fn toggle_timer(app: &AppHandle) -> anyhow::Result<AppState> {
+    let app_state = app.state::<StateService>().0.toggle_timer().await?;
+    timer_changed(app, &app_state)?;
+
+    Ok(app_state)
+}
+
+/// Tells the tray and the windows that the timer started or stopped
+pub fn timer_changed(app: &AppHandle, app_state: &AppState) -> anyhow::Result<()> {
+    if let Err(e) = update_timer_menu_item(app, app_state.timer_started) {
+        eprintln!("Failed to update timer menu item: {}", e);
+    }
+
+    app.emit("update-app-state", app_state.clone())?;
+
+    Ok(())
+}
@@ -1,15 +1,22 @@
 use std::sync::Mutex as SyncMutex;
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::{
+    image::Image,
+    include_image,
     menu::{Menu, MenuItem, PredefinedMenuItem},
-    AppHandle, Manager,
+    AppHandle, Listener, Manager,
 };
-use tokio::sync::Mutex;
 
-use crate::settings::AppSettings;
-use crate::state::{AppTimerState, SettingsState, SipTrackingState};
-use crate::IgnorePoisoned;
-use crate::{db::DatabaseState, sip::SipState, AppState};
+use waterer_core::{
+    events::TOTALS_CHANGED,
+    sip::{SipSource, SipState},
+    state_actor::LoggedSip,
+    streak::Streaks,
+};
+
+use crate::sip::{confirm_sip, log_sip};
+use crate::state::StateService;
+use crate::timer::toggle_timer;
 
 // Global storage for menu items so they can be updated from anywhere
 static MENU_ITEMS: SyncMutex<Option<(MenuItem<tauri::Wry>, MenuItem<tauri::Wry>)>> =
@@ -29,22 +36,60 @@ pub fn update_timer_menu_item(_app_handle: &AppHandle, timer_started: bool) -> a
     Ok(())
 }
 
-pub fn update_sip_menu_item(_app_handle: &AppHandle, total_amount: i64) -> anyhow::Result<()> {
+fn sip_menu_text(total_amount: i64, goal_reached: bool) -> String {
+    if goal_reached {
+        format!("Sip ({}ml today, goal reached)", total_amount)
+    } else {
+        format!("Sip ({}ml today)", total_amount)
+    }
+}
+
+fn is_goal_reached(app_handle: &AppHandle, total_amount: i64) -> bool {
+    let settings = app_handle.state::<StateService>().0.snapshot().settings;
+    total_amount >= settings.daily_goal_ml
+}
+
+fn tray_icon(app_handle: &AppHandle, goal_reached: bool) -> Image<'static> {
+    if goal_reached {
+        include_image!("icons/tray-goal-reached.png")
+    } else {
+        app_handle.default_window_icon().unwrap().clone()
+    }
+}
+
+/// Shows today's total in the sip item and switches the tray icon once the
+/// daily goal is reached
+pub fn update_sip_menu_item(app_handle: &AppHandle, total_amount: i64) -> anyhow::Result<()> {
+    let goal_reached = is_goal_reached(app_handle, total_amount);
+
     if let Ok(guard) = MENU_ITEMS.lock() {
         if let Some((_, sip_item)) = guard.as_ref() {
-            let text = format!("Sip ({}ml today)", total_amount);
-            sip_item.set_text(&text)?;
+            sip_item.set_text(sip_menu_text(total_amount, goal_reached))?;
         }
     }
+
+    if let Some(tray) = app_handle.tray_by_id("main") {
+        tray.set_icon(Some(tray_icon(app_handle, goal_reached)))?;
+    }
+    Ok(())
+}
+
+pub fn update_streak_tooltip(app_handle: &AppHandle, streaks: &Streaks) -> anyhow::Result<()> {
+    if let Some(tray) = app_handle.tray_by_id("main") {
+        let text = format!(
+            "{}\n{} day streak (best {})",
+            app_handle.package_info().name,
+            streaks.current_days,
+            streaks.longest_days
+        );
+        tray.set_tooltip(Some(&text))?;
+    }
     Ok(())
 }
 
 pub fn create_tray(app_handle: &AppHandle) -> anyhow::Result<()> {
-    let timer_started = {
-        let app_state = app_handle.state::<AppTimerState>();
-        let app_state = app_state.lock().unwrap();
-        app_state.timer_started
-    };
+    let snapshot = app_handle.state::<StateService>().0.snapshot();
+    let timer_started = snapshot.app_state.timer_started;
 
     let menu_item_show = MenuItem::with_id(app_handle, "show", "Show", true, None::<&str>)?;
     let menu_separator = PredefinedMenuItem::separator(app_handle)?;
@@ -57,14 +102,12 @@ pub fn create_tray(app_handle: &AppHandle) -> anyhow::Result<()> {
     };
     let menu_item_timer = MenuItem::with_id(app_handle, "start", timer_text, true, None::<&str>)?;
 
-    let total_sip_amount_today = {
-        let sip_state = app_handle.state::<SipTrackingState>();
-        let locked_sip_state = tauri::async_runtime::block_on(async { sip_state.lock().await });
-        locked_sip_state.total_amount_today
-    };
+    let total_sip_amount_today = snapshot.sip_state.total_amount_today;
+
+    let goal_reached = is_goal_reached(app_handle, total_sip_amount_today);
 
     // Dynamic sip menu item with current state info
-    let sip_text = format!("Sip ({}ml today)", total_sip_amount_today);
+    let sip_text = sip_menu_text(total_sip_amount_today, goal_reached);
     let menu_item_sip = MenuItem::with_id(app_handle, "sip", &sip_text, true, None::<&str>)?;
 
     let menu_item_quit = MenuItem::with_id(app_handle, "quit", "Quit", true, None::<&str>)?;
@@ -87,12 +130,26 @@ pub fn create_tray(app_handle: &AppHandle) -> anyhow::Result<()> {
         ],
     )?;
 
+    // Keep the sip item in line with the totals, whichever path changed them
+    let listener_handle = app_handle.clone();
+    app_handle.listen(TOTALS_CHANGED, move |event| {
+        match serde_json::from_str::<SipState>(event.payload()) {
+            Ok(sip_state) => {
+                if let Err(e) = update_sip_menu_item(&listener_handle, sip_state.total_amount_today)
+                {
+                    eprintln!("Failed to update tray menu: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to read {} payload: {}", TOTALS_CHANGED, e),
+        }
+    });
+
     let tray_on_left_click = tauri_plugin_os::platform() == "macos";
 
     let app_handle = app_handle.clone();
 
     _ = TrayIconBuilder::with_id("main")
-        .icon(app_handle.default_window_icon().unwrap().clone())
+        .icon(tray_icon(&app_handle, goal_reached))
         .tooltip(app_handle.package_info().name.clone())
         .menu(&menu)
         .show_menu_on_left_click(tray_on_left_click)
@@ -109,73 +166,30 @@ pub fn create_tray(app_handle: &AppHandle) -> anyhow::Result<()> {
                 }
             }
             "start" => {
-                use tauri::Emitter;
-
                 println!("start menu item was clicked");
-                let app_state = app.state::<AppTimerState>();
-                let mut app_state = app_state.lock().unwrap();
-                if app_state.timer_started {
-                    app_state.stop_timer();
-                } else {
-                    app_state.start_timer();
-                }
-
-                // Update the tray menu item
-                if let Err(e) = update_timer_menu_item(&app, app_state.timer_started) {
-                    eprintln!("Failed to update timer menu item: {}", e);
-                }
 
-                if let Err(e) = app.emit("update-app-state", app_state.clone()) {
-                    eprintln!("Failed to update app_state: {}", e);
-                }
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = toggle_timer(&app).await {
+                        eprintln!("Failed to toggle timer: {}", e);
+                    }
+                });
             }
             "sip" => {
                 println!("sip menu item was clicked");
-                let db_state = app.state::<DatabaseState>();
-                let pool = &db_state.0;
-
-                let result = tauri::async_runtime::block_on(async {
-                    let sip_state = app.state::<SipTrackingState>();
-                    let mut locked_sip_state = sip_state.lock().await;
-
-                    let settings = app.state::<SettingsState>();
-                    let settings = settings.lock().ignore_poisoned();
-
-                    let session_id = {
-                        let app_state = app.state::<AppTimerState>();
-                        let state = app_state.lock().ignore_poisoned();
-                        state.session_id
-                    };
-
-                    let session_id = match session_id {
-                        Some(id) => id,
-                        None => {
-                            eprintln!("No session ID available");
-                            return;
-                        }
-                    };
-
-                    match locked_sip_state
-                        .take_sip(settings.sip_amount_ml, pool, session_id)
-                        .await
-                    {
-                        Ok(new_state) => {
-                            *locked_sip_state = new_state;
-                            println!("Updated sip state");
-
-                            if let Err(e) =
-                                update_sip_menu_item(&app, locked_sip_state.total_amount_today)
-                            {
-                                eprintln!("Failed to update tray menu: {}", e);
-                            }
+
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    match log_sip(&app, None, SipSource::Tray, None, false).await {
+                        Ok(LoggedSip::Logged { .. } | LoggedSip::AlreadyLogged(_)) => {
+                            println!("Updated sip state")
                         }
-                        Err(e) => {
-                            eprintln!("Failed to take sip: {}", e);
+                        Ok(LoggedSip::NeedsConfirmation(check)) => {
+                            confirm_sip(&app, &check, None, SipSource::Tray)
                         }
+                        Err(e) => eprintln!("Failed to take sip: {}", e),
                     }
                 });
-
-                println!("result {:#?}", result)
             }
             _ => {
                 println!("menu item {:?} not handled", event.id);
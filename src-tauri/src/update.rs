@@ -1,5 +1,8 @@
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
 use tauri_plugin_updater::UpdaterExt;
+use waterer_core::session::SessionEndReason;
+
+use crate::session::end_session;
 
 //TODO: check if anyhow error also gets the strings
 pub async fn update(app: &tauri::AppHandle) -> anyhow::Result<()> {
@@ -34,6 +37,11 @@ pub async fn update(app: &tauri::AppHandle) -> anyhow::Result<()> {
             .await?;
 
         update.install(downloaded_update)?;
+
+        // Restarting skips the exit event, so the session is closed here
+        if let Err(e) = end_session(app, SessionEndReason::Update).await {
+            eprintln!("Failed to end session: {}", e);
+        }
         app.restart();
     }
 
@@ -0,0 +1,83 @@
+//! Compares the aggregate-based sip totals with a full table scan on a
+//! database of a million synthetic sips, and times incrementally maintained
+//! totals. The `daily_totals` triggers are covered by the repository tests.
+//!
+//! Run with `cargo run --release -p waterer-core --example sip_totals_bench`.
+
+use std::time::Instant;
+
+use waterer_core::{
+    clock::{Clock, SystemClock},
+    db::Database,
+    repository::{DailyTotalsRepository, SessionRepository, SipRepository, SqliteRepository},
+    sip::{SipOrigin, SipSource, SipState},
+};
+
+const SYNTHETIC_SIPS: i64 = 1_000_000;
+const INCREMENTAL_SIPS: usize = 1_000;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let clock = SystemClock;
+    let database = Database::in_memory().await?;
+    let repo = SqliteRepository::new(database.pool);
+
+    let session_id = repo
+        .insert_session("00000000-0000-0000-0000-00000000bench", clock.now())
+        .await?;
+
+    let started = Instant::now();
+    // One sip a minute going back roughly two years
+    sqlx::query(
+        "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < ?)
+         INSERT INTO sips (amount, session_id, created_at, created_at_local)
+         SELECT 25 + n % 200, ?, datetime('now', printf('-%d minutes', n)),
+                datetime('now', printf('-%d minutes', n), 'localtime')
+         FROM seq",
+    )
+    .bind(SYNTHETIC_SIPS)
+    .bind(session_id)
+    .execute(repo.pool())
+    .await?;
+    println!("seeded {SYNTHETIC_SIPS} sips in {:?}", started.elapsed());
+
+    let started = Instant::now();
+    let sips = repo.all_sips().await?;
+    let scanned_amount: i64 = sips.iter().map(|sip| sip.amount).sum();
+    println!("full table scan: {:?}", started.elapsed());
+
+    let started = Instant::now();
+    let mut state = SipState::default().read_from_db(&repo, &clock).await;
+    println!("aggregate read: {:?}", started.elapsed());
+
+    let all_time = repo.all_time_totals().await?;
+    assert_eq!(all_time.amount, scanned_amount);
+
+    let started = Instant::now();
+    for _ in 0..INCREMENTAL_SIPS {
+        state = state
+            .take_sip(
+                50,
+                SipOrigin {
+                    session_id,
+                    run_id: None,
+                    source: SipSource::Automation,
+                    request_id: None,
+                },
+                &repo,
+                &clock,
+            )
+            .await?;
+    }
+    println!(
+        "{INCREMENTAL_SIPS} incremental sips: {:?} ({:?} per sip)",
+        started.elapsed(),
+        started.elapsed() / INCREMENTAL_SIPS as u32
+    );
+
+    let in_sync = state.reconcile(&repo, &clock).await;
+    println!("incremental totals match the database: {in_sync}");
+    assert!(in_sync);
+
+    Ok(())
+}
@@ -0,0 +1,3 @@
+-- Add migration script here
+
+CREATE INDEX IF NOT EXISTS idx_sips_created_at ON sips (created_at);
@@ -0,0 +1,74 @@
+-- Add migration script here
+
+-- The daily goal in effect, mirrored from the settings file so triggers can
+-- stamp it onto each new day
+CREATE TABLE IF NOT EXISTS daily_goal (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    goal_ml INTEGER NOT NULL
+);
+
+INSERT OR IGNORE INTO daily_goal (id, goal_ml) VALUES (1, 2000);
+
+-- One row per local calendar day that has at least one sip
+CREATE TABLE IF NOT EXISTS daily_totals (
+    date TEXT PRIMARY KEY NOT NULL,
+    total_ml INTEGER NOT NULL DEFAULT 0,
+    sip_count INTEGER NOT NULL DEFAULT 0,
+    goal_ml INTEGER NOT NULL,
+    goal_met BOOLEAN GENERATED ALWAYS AS (total_ml >= goal_ml) VIRTUAL
+);
+
+INSERT INTO daily_totals (date, total_ml, sip_count, goal_ml)
+SELECT date(created_at, 'localtime'), SUM(amount), COUNT(*), (SELECT goal_ml FROM daily_goal WHERE id = 1)
+FROM sips
+GROUP BY date(created_at, 'localtime');
+
+CREATE TRIGGER IF NOT EXISTS daily_totals_after_sip_insert
+AFTER INSERT ON sips
+BEGIN
+    INSERT INTO daily_totals (date, total_ml, sip_count, goal_ml)
+    VALUES (
+        date(NEW.created_at, 'localtime'),
+        NEW.amount,
+        1,
+        (SELECT goal_ml FROM daily_goal WHERE id = 1)
+    )
+    ON CONFLICT (date) DO UPDATE SET
+        total_ml = total_ml + excluded.total_ml,
+        sip_count = sip_count + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS daily_totals_after_sip_delete
+AFTER DELETE ON sips
+BEGIN
+    UPDATE daily_totals
+    SET total_ml = total_ml - OLD.amount,
+        sip_count = sip_count - 1
+    WHERE date = date(OLD.created_at, 'localtime');
+
+    DELETE FROM daily_totals
+    WHERE date = date(OLD.created_at, 'localtime') AND sip_count <= 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS daily_totals_after_sip_update
+AFTER UPDATE OF amount, created_at ON sips
+BEGIN
+    UPDATE daily_totals
+    SET total_ml = total_ml - OLD.amount,
+        sip_count = sip_count - 1
+    WHERE date = date(OLD.created_at, 'localtime');
+
+    DELETE FROM daily_totals
+    WHERE date = date(OLD.created_at, 'localtime') AND sip_count <= 0;
+
+    INSERT INTO daily_totals (date, total_ml, sip_count, goal_ml)
+    VALUES (
+        date(NEW.created_at, 'localtime'),
+        NEW.amount,
+        1,
+        (SELECT goal_ml FROM daily_goal WHERE id = 1)
+    )
+    ON CONFLICT (date) DO UPDATE SET
+        total_ml = total_ml + excluded.total_ml,
+        sip_count = sip_count + 1;
+END;
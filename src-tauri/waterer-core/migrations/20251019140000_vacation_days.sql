@@ -0,0 +1,6 @@
+-- Add migration script here
+
+-- Local calendar days that neither extend nor break a streak
+CREATE TABLE IF NOT EXISTS vacation_days (
+    date TEXT PRIMARY KEY NOT NULL
+);
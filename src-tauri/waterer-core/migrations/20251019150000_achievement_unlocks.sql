@@ -0,0 +1,7 @@
+-- Add migration script here
+
+-- Achievement rules live in data files, only their unlocks are stored here
+CREATE TABLE IF NOT EXISTS achievement_unlocks (
+    achievement_id TEXT PRIMARY KEY NOT NULL,
+    unlocked_at TEXT NOT NULL
+);
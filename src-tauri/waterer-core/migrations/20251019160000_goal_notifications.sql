@@ -0,0 +1,9 @@
+-- Add migration script here
+
+-- Goal progress notifications already shown, so each fires once per day
+CREATE TABLE IF NOT EXISTS goal_notifications (
+    date TEXT NOT NULL,
+    percent INTEGER NOT NULL,
+    notified_at TEXT NOT NULL,
+    PRIMARY KEY (date, percent)
+);
@@ -0,0 +1,6 @@
+-- Add migration script here
+
+-- Id chosen by the client so a retried request doesn't log the sip twice
+ALTER TABLE sips ADD COLUMN request_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sips_request_id ON sips (request_id);
@@ -0,0 +1,4 @@
+-- Add migration script here
+
+-- Where a sip was logged from: tray, main_window, notification, automation
+ALTER TABLE sips ADD COLUMN source TEXT NOT NULL DEFAULT 'unknown';
@@ -0,0 +1,9 @@
+-- Add migration script here
+
+-- When and how a session ended. Both stay NULL while the app is running.
+ALTER TABLE sessions ADD COLUMN session_end TEXT;
+ALTER TABLE sessions ADD COLUMN end_reason TEXT;
+
+-- Updated periodically while the app runs, so a crashed session can be
+-- closed at the last moment it was known to be alive
+ALTER TABLE sessions ADD COLUMN last_heartbeat TEXT;
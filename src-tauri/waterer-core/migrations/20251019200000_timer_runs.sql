@@ -0,0 +1,18 @@
+-- Add migration script here
+
+-- One row per "timer started -> timer stopped" interval within a session
+CREATE TABLE IF NOT EXISTS timer_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    -- NULL while the timer is running
+    stopped_at TEXT,
+    FOREIGN KEY(session_id) REFERENCES sessions(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_timer_runs_session_id ON timer_runs (session_id);
+
+-- The run that was active when the sip was logged, NULL if the timer was stopped
+ALTER TABLE sips ADD COLUMN run_id INTEGER REFERENCES timer_runs(id);
+
+CREATE INDEX IF NOT EXISTS idx_sips_run_id ON sips (run_id);
@@ -0,0 +1,10 @@
+-- Add migration script here
+
+-- Whether the timer was running when the app last ran, so it can be restored
+-- after a restart
+CREATE TABLE IF NOT EXISTS timer_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    timer_started BOOLEAN NOT NULL
+);
+
+INSERT OR IGNORE INTO timer_state (id, timer_started) VALUES (1, FALSE);
@@ -0,0 +1,14 @@
+-- Add migration script here
+
+-- What the user did with a reminder notification: one of its actions, or
+-- dismissing it
+CREATE TABLE IF NOT EXISTS reminder_outcomes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    fired_at TEXT NOT NULL,
+    action TEXT NOT NULL,
+    acted_at TEXT NOT NULL,
+    -- The sip logged by the action, if it logged one
+    sip_id INTEGER REFERENCES sips(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminder_outcomes_fired_at ON reminder_outcomes (fired_at);
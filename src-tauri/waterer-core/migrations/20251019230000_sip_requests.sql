@@ -0,0 +1,8 @@
+-- Add migration script here
+
+-- Request ids of sips that were added to an earlier sip instead of stored on
+-- their own, so a retried merge is recognised like a retried insert
+CREATE TABLE IF NOT EXISTS sip_requests (
+    request_id TEXT PRIMARY KEY NOT NULL,
+    sip_id INTEGER NOT NULL REFERENCES sips (id) ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+-- Add migration script here
+
+-- Sessions without a heartbeat are from before the app recorded how sessions
+-- end. Recovery closed them as crashed, but they may just as well have been
+-- quit.
+UPDATE sessions SET end_reason = 'unknown'
+WHERE last_heartbeat IS NULL AND end_reason = 'crashed';
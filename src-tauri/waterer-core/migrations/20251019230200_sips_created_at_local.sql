@@ -0,0 +1,66 @@
+-- Add migration script here
+
+-- The wall clock time a sip was taken at, in the time zone the app was
+-- using. Local days come from here rather than from created_at and SQLite's
+-- 'localtime', which only knows the zone of the process.
+ALTER TABLE sips ADD COLUMN created_at_local TEXT;
+
+-- The process's zone is the best guess for sips logged before
+UPDATE sips SET created_at_local = datetime(created_at, 'localtime');
+
+CREATE INDEX IF NOT EXISTS idx_sips_created_at_local ON sips (created_at_local);
+
+DROP TRIGGER IF EXISTS daily_totals_after_sip_insert;
+DROP TRIGGER IF EXISTS daily_totals_after_sip_delete;
+DROP TRIGGER IF EXISTS daily_totals_after_sip_update;
+
+CREATE TRIGGER IF NOT EXISTS daily_totals_after_sip_insert
+AFTER INSERT ON sips
+BEGIN
+    INSERT INTO daily_totals (date, total_ml, sip_count, goal_ml)
+    VALUES (
+        date(NEW.created_at_local),
+        NEW.amount,
+        1,
+        (SELECT goal_ml FROM daily_goal WHERE id = 1)
+    )
+    ON CONFLICT (date) DO UPDATE SET
+        total_ml = total_ml + excluded.total_ml,
+        sip_count = sip_count + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS daily_totals_after_sip_delete
+AFTER DELETE ON sips
+BEGIN
+    UPDATE daily_totals
+    SET total_ml = total_ml - OLD.amount,
+        sip_count = sip_count - 1
+    WHERE date = date(OLD.created_at_local);
+
+    DELETE FROM daily_totals
+    WHERE date = date(OLD.created_at_local) AND sip_count <= 0;
+END;
+
+-- Moving a sip means updating created_at_local along with created_at
+CREATE TRIGGER IF NOT EXISTS daily_totals_after_sip_update
+AFTER UPDATE OF amount, created_at_local ON sips
+BEGIN
+    UPDATE daily_totals
+    SET total_ml = total_ml - OLD.amount,
+        sip_count = sip_count - 1
+    WHERE date = date(OLD.created_at_local);
+
+    DELETE FROM daily_totals
+    WHERE date = date(OLD.created_at_local) AND sip_count <= 0;
+
+    INSERT INTO daily_totals (date, total_ml, sip_count, goal_ml)
+    VALUES (
+        date(NEW.created_at_local),
+        NEW.amount,
+        1,
+        (SELECT goal_ml FROM daily_goal WHERE id = 1)
+    )
+    ON CONFLICT (date) DO UPDATE SET
+        total_ml = total_ml + excluded.total_ml,
+        sip_count = sip_count + 1;
+END;
@@ -0,0 +1,13 @@
+-- Add migration script here
+
+-- Sessions used to store their start as RFC 3339 while every other timestamp
+-- is in SQLite's 'YYYY-MM-DD HH:MM:SS' UTC format, so they could not be
+-- compared. Recovery copied that start into session_end for sessions without
+-- a heartbeat or sip.
+UPDATE sessions SET session_start = datetime(session_start)
+WHERE datetime(session_start) IS NOT NULL
+  AND session_start != datetime(session_start);
+
+UPDATE sessions SET session_end = datetime(session_end)
+WHERE datetime(session_end) IS NOT NULL
+  AND session_end != datetime(session_end);
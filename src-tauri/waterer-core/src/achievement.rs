@@ -0,0 +1,513 @@
+use std::{collections::HashMap, fs, future::Future};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::{
+    clock::Clock, db::format_timestamp, repository::SqliteRepository, settings::config_dir,
+    sip::SipState,
+};
+
+/// Rules shipped with the app
+const BUILTIN_ACHIEVEMENTS: &str = include_str!("../achievements.toml");
+
+/// What has to hold for an achievement to unlock
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    DailyTotalAtLeast {
+        ml: i64,
+    },
+    TotalVolumeAtLeast {
+        ml: i64,
+    },
+    TotalSipsAtLeast {
+        count: i64,
+    },
+    StreakAtLeast {
+        days: i64,
+    },
+    /// A sip logged before this local time of day
+    SipBefore {
+        time: NaiveTime,
+    },
+    /// A day started earlier than any of the at least `min_days` days with
+    /// sips before it
+    EarliestFirstSip {
+        min_days: i64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AchievementRule {
+    /// Stable key stored with the unlock, never reuse it for another rule
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Deserialize)]
+struct AchievementFile {
+    #[serde(default)]
+    achievement: Vec<AchievementRule>,
+}
+
+/// When the days before a given one started
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromRow)]
+pub struct FirstSips {
+    /// The earliest local time of day any of those days had its first sip at
+    pub earliest: Option<NaiveTime>,
+    /// How many of those days have sips
+    pub days: i64,
+}
+
+/// Everything the rules are evaluated against, taken right after a sip
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AchievementContext {
+    pub total_amount_today: i64,
+    pub total_amount_all_time: i64,
+    pub total_sips_all_time: i64,
+    pub current_streak_days: i64,
+    /// Local time of the sip that triggered the evaluation
+    pub sip_time: Option<NaiveTime>,
+    /// Whether that sip is the first of its day
+    pub first_sip_of_day: bool,
+    /// The first sips of the days before it
+    pub earlier_first_sips: FirstSips,
+}
+
+impl AchievementContext {
+    /// The context after the latest sip of `sip_state`, with times of day in
+    /// the clock's zone
+    pub async fn load(
+        sip_state: &SipState,
+        current_streak_days: i64,
+        repo: &impl AchievementRepository,
+        clock: &dyn Clock,
+    ) -> Result<Self, sqlx::Error> {
+        let sip_at = sip_state
+            .last_sip_at()
+            .map(|at| at.with_timezone(&clock.zone()));
+        let earlier_first_sips = match sip_at {
+            Some(at) => repo.first_sips_before(at.date_naive()).await?,
+            None => FirstSips::default(),
+        };
+
+        Ok(Self {
+            total_amount_today: sip_state.total_amount_today,
+            total_amount_all_time: sip_state.total_amount_all_time,
+            total_sips_all_time: sip_state.total_sips_all_time,
+            current_streak_days,
+            sip_time: sip_at.map(|at| at.time()),
+            first_sip_of_day: sip_state.total_sips_today == 1,
+            earlier_first_sips,
+        })
+    }
+}
+
+impl Condition {
+    pub fn is_met(&self, context: &AchievementContext) -> bool {
+        match self {
+            Condition::DailyTotalAtLeast { ml } => context.total_amount_today >= *ml,
+            Condition::TotalVolumeAtLeast { ml } => context.total_amount_all_time >= *ml,
+            Condition::TotalSipsAtLeast { count } => context.total_sips_all_time >= *count,
+            Condition::StreakAtLeast { days } => context.current_streak_days >= *days,
+            Condition::SipBefore { time } => context.sip_time.is_some_and(|at| at < *time),
+            Condition::EarliestFirstSip { min_days } => {
+                let earlier = context.earlier_first_sips;
+                context.first_sip_of_day
+                    && earlier.days >= *min_days
+                    && context
+                        .sip_time
+                        .zip(earlier.earliest)
+                        .is_some_and(|(at, earliest)| at < earliest)
+            }
+        }
+    }
+}
+
+/// An achievement as shown to the user
+#[derive(Debug, Clone, Serialize)]
+pub struct Achievement {
+    #[serde(flatten)]
+    pub rule: AchievementRule,
+    pub unlocked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AchievementUnlock {
+    pub achievement_id: String,
+    pub unlocked_at: DateTime<Utc>,
+}
+
+/// Storage for unlocked achievements. Rules are not stored, so adding one
+/// never needs a migration.
+pub trait AchievementRepository {
+    fn achievement_unlocks(
+        &self,
+    ) -> impl Future<Output = Result<Vec<AchievementUnlock>, sqlx::Error>> + Send;
+
+    /// When the days with sips before the local day `date` started
+    fn first_sips_before(
+        &self,
+        date: NaiveDate,
+    ) -> impl Future<Output = Result<FirstSips, sqlx::Error>> + Send;
+
+    /// Records an unlock, returns false if it was already unlocked
+    fn unlock_achievement(
+        &self,
+        achievement_id: &str,
+        unlocked_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+}
+
+impl AchievementRepository for SqliteRepository {
+    async fn achievement_unlocks(&self) -> Result<Vec<AchievementUnlock>, sqlx::Error> {
+        sqlx::query_as::<_, AchievementUnlock>(
+            "SELECT * FROM achievement_unlocks ORDER BY unlocked_at",
+        )
+        .fetch_all(self.pool())
+        .await
+    }
+
+    async fn first_sips_before(&self, date: NaiveDate) -> Result<FirstSips, sqlx::Error> {
+        // A bare date sorts before every time on that day
+        sqlx::query_as::<_, FirstSips>(
+            "SELECT MIN(first_sip) AS earliest, COUNT(*) AS days
+             FROM (
+                 SELECT time(MIN(created_at_local)) AS first_sip
+                 FROM sips
+                 WHERE created_at_local < ?
+                 GROUP BY date(created_at_local)
+             )",
+        )
+        .bind(date.to_string())
+        .fetch_one(self.pool())
+        .await
+    }
+
+    async fn unlock_achievement(
+        &self,
+        achievement_id: &str,
+        unlocked_at: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let unlocked_at = format_timestamp(unlocked_at);
+
+        let result = sqlx::query!(
+            "INSERT OR IGNORE INTO achievement_unlocks (achievement_id, unlocked_at) VALUES (?, ?)",
+            achievement_id,
+            unlocked_at
+        )
+        .execute(self.pool())
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// The set of achievement rules in effect
+#[derive(Debug, Clone, Default)]
+pub struct AchievementRules {
+    rules: Vec<AchievementRule>,
+}
+
+impl AchievementRules {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let file: AchievementFile = toml::from_str(source)?;
+        let mut rules = Self::default();
+        rules.merge(file.achievement);
+        Ok(rules)
+    }
+
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_ACHIEVEMENTS).expect("built-in achievements are valid")
+    }
+
+    /// Built-in rules plus those from `achievements.toml` in the config
+    /// directory, which win on matching ids
+    pub fn load() -> Self {
+        let mut rules = Self::builtin();
+
+        let path = config_dir().join("achievements.toml");
+        if !path.exists() {
+            return rules;
+        }
+
+        match fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|source| Self::parse(&source))
+        {
+            Ok(custom) => rules.merge(custom.rules),
+            Err(e) => eprintln!("Failed to load {}: {}", path.display(), e),
+        }
+
+        rules
+    }
+
+    fn merge(&mut self, rules: Vec<AchievementRule>) {
+        for rule in rules {
+            match self
+                .rules
+                .iter_mut()
+                .find(|existing| existing.id == rule.id)
+            {
+                Some(existing) => *existing = rule,
+                None => self.rules.push(rule),
+            }
+        }
+    }
+
+    pub fn rules(&self) -> &[AchievementRule] {
+        &self.rules
+    }
+
+    /// Rules met by `context` that are not unlocked yet
+    pub fn newly_met<'a>(
+        &'a self,
+        context: &AchievementContext,
+        unlocks: &[AchievementUnlock],
+    ) -> Vec<&'a AchievementRule> {
+        self.rules
+            .iter()
+            .filter(|rule| {
+                !unlocks
+                    .iter()
+                    .any(|unlock| unlock.achievement_id == rule.id)
+            })
+            .filter(|rule| rule.condition.is_met(context))
+            .collect()
+    }
+
+    /// Every rule with its unlock time, if any
+    pub fn with_unlocks(&self, unlocks: &[AchievementUnlock]) -> Vec<Achievement> {
+        let unlocked_at: HashMap<&str, DateTime<Utc>> = unlocks
+            .iter()
+            .map(|unlock| (unlock.achievement_id.as_str(), unlock.unlocked_at))
+            .collect();
+
+        self.rules
+            .iter()
+            .map(|rule| Achievement {
+                rule: rule.clone(),
+                unlocked_at: unlocked_at.get(rule.id.as_str()).copied(),
+            })
+            .collect()
+    }
+}
+
+/// Evaluates the rules and stores new unlocks, returning the rules that
+/// were unlocked by this call
+pub async fn unlock_achievements(
+    rules: &AchievementRules,
+    repo: &impl AchievementRepository,
+    context: &AchievementContext,
+    now: DateTime<Utc>,
+) -> Result<Vec<AchievementRule>, sqlx::Error> {
+    let unlocks = repo.achievement_unlocks().await?;
+
+    let mut unlocked = Vec::new();
+    for rule in rules.newly_met(context, &unlocks) {
+        if repo.unlock_achievement(&rule.id, now).await? {
+            unlocked.push(rule.clone());
+        }
+    }
+
+    Ok(unlocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Pacific::Kiritimati;
+
+    use super::*;
+    use crate::{
+        clock::{at_local, ManualClock},
+        db::Database,
+        repository::{SessionRepository, SqliteRepository},
+        sip::{SipOrigin, SipSource},
+    };
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn met_ids(rules: &AchievementRules, context: &AchievementContext) -> Vec<String> {
+        rules
+            .newly_met(context, &[])
+            .into_iter()
+            .map(|rule| rule.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn builtin_rules_unlock_on_their_thresholds() {
+        let rules = AchievementRules::builtin();
+
+        let first = AchievementContext {
+            total_amount_today: 250,
+            total_amount_all_time: 250,
+            total_sips_all_time: 1,
+            ..AchievementContext::default()
+        };
+        assert_eq!(met_ids(&rules, &first), vec!["first_sip"]);
+
+        let busy = AchievementContext {
+            total_amount_today: 2000,
+            total_amount_all_time: 100_000,
+            total_sips_all_time: 100,
+            current_streak_days: 7,
+            ..AchievementContext::default()
+        };
+        assert_eq!(
+            met_ids(&rules, &busy),
+            vec![
+                "first_sip",
+                "two_litre_day",
+                "week_streak",
+                "hundred_sips",
+                "hundred_litres"
+            ]
+        );
+
+        let almost = AchievementContext {
+            total_amount_today: 1999,
+            total_amount_all_time: 99_999,
+            total_sips_all_time: 99,
+            current_streak_days: 6,
+            ..AchievementContext::default()
+        };
+        assert_eq!(met_ids(&rules, &almost), vec!["first_sip"]);
+    }
+
+    #[test]
+    fn unlocked_rules_are_not_met_again() {
+        let rules = AchievementRules::builtin();
+        let context = AchievementContext {
+            total_sips_all_time: 1,
+            ..AchievementContext::default()
+        };
+        let unlocks = [AchievementUnlock {
+            achievement_id: "first_sip".to_string(),
+            unlocked_at: Utc::now(),
+        }];
+
+        assert!(rules.newly_met(&context, &unlocks).is_empty());
+    }
+
+    #[test]
+    fn the_earliest_first_sip_needs_enough_earlier_days() {
+        let condition = Condition::EarliestFirstSip { min_days: 7 };
+        let context = |sip_time, first_sip_of_day, days| AchievementContext {
+            sip_time: Some(sip_time),
+            first_sip_of_day,
+            earlier_first_sips: FirstSips {
+                earliest: Some(time(7, 30)),
+                days,
+            },
+            ..AchievementContext::default()
+        };
+
+        assert!(condition.is_met(&context(time(7, 29), true, 7)));
+        assert!(!condition.is_met(&context(time(7, 30), true, 7)));
+        assert!(!condition.is_met(&context(time(6, 0), true, 6)));
+        // Only the sip that starts the day counts
+        assert!(!condition.is_met(&context(time(6, 0), false, 7)));
+    }
+
+    #[test]
+    fn custom_rules_replace_builtin_ones_with_the_same_id() {
+        let mut rules = AchievementRules::builtin();
+        let custom = AchievementRules::parse(
+            r#"
+            [[achievement]]
+            id = "two_litre_day"
+            title = "Three litre day"
+            description = "Drink 3 litres in a single day"
+            condition = { type = "daily_total_at_least", ml = 3000 }
+
+            [[achievement]]
+            id = "night_owl"
+            title = "Night owl"
+            description = "A sip before 4 in the morning"
+            condition = { type = "sip_before", time = "04:00:00" }
+            "#,
+        )
+        .unwrap();
+        let count = rules.rules().len();
+        rules.merge(custom.rules);
+
+        assert_eq!(rules.rules().len(), count + 1);
+        let two_litre = rules
+            .rules()
+            .iter()
+            .find(|rule| rule.id == "two_litre_day")
+            .unwrap();
+        assert_eq!(
+            two_litre.condition,
+            Condition::DailyTotalAtLeast { ml: 3000 }
+        );
+        assert!(AchievementRules::parse("[[achievement]]\nid = 1").is_err());
+    }
+
+    #[tokio::test]
+    async fn starting_a_day_earlier_than_ever_unlocks_once() {
+        let repo = SqliteRepository::new(Database::in_memory().await.unwrap().pool);
+        // Mornings in Kiritimati are still the day before in UTC
+        let clock = ManualClock::new("2025-06-09T18:00:00Z".parse().unwrap()).in_zone(Kiritimati);
+        let session_id = repo.insert_session("test", clock.now()).await.unwrap();
+        let origin = SipOrigin {
+            session_id,
+            run_id: None,
+            source: SipSource::Tray,
+            request_id: None,
+        };
+        let rules = AchievementRules::parse(
+            r#"
+            [[achievement]]
+            id = "earliest_sip"
+            title = "Early bird"
+            description = "Start a day earlier than ever"
+            condition = { type = "earliest_first_sip", min_days = 2 }
+            "#,
+        )
+        .unwrap();
+
+        // Two days starting at 08:00 and 08:30, then one starting at 07:30
+        let mut state = SipState::default();
+        let mut unlocked = Vec::new();
+        for (day, hour, minute) in [
+            (10, 8, 0),
+            (10, 14, 0),
+            (11, 8, 30),
+            (11, 12, 0),
+            (12, 7, 30),
+            (12, 7, 45),
+        ] {
+            let date = NaiveDate::from_ymd_opt(2025, 6, day).unwrap();
+            let at = at_local(&clock.zone(), date, time(hour, minute)).unwrap();
+            clock.set(at.to_utc());
+            state = state.take_sip(100, origin, &repo, &clock).await.unwrap();
+
+            let context = AchievementContext::load(&state, 0, &repo, &clock)
+                .await
+                .unwrap();
+            let newly = unlock_achievements(&rules, &repo, &context, clock.now())
+                .await
+                .unwrap();
+            unlocked.push(newly.len());
+        }
+
+        assert_eq!(unlocked, vec![0, 0, 0, 0, 1, 0]);
+        assert_eq!(
+            repo.first_sips_before(clock.local_now().date_naive())
+                .await
+                .unwrap(),
+            FirstSips {
+                earliest: Some(time(8, 0)),
+                days: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    clock::Clock,
+    repository::SessionRepository,
+    settings::{AppSettings, TimerAutoStart},
+};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AppState {
+    pub timer_started: bool,
+    pub session_id: Option<i64>,
+    pub session_start: DateTime<Utc>,
+    /// The timer run in progress, sips are attributed to it
+    pub run_id: Option<i64>,
+    /// Reminders are held until then
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+impl AppState {
+    pub fn new(clock: &dyn Clock) -> Self {
+        Self {
+            timer_started: false,
+            session_id: None,
+            session_start: clock.now(),
+            run_id: None,
+            snoozed_until: None,
+        }
+    }
+
+    /// Starts the timer and records a new run of the current session
+    pub async fn start_timer(
+        &mut self,
+        repo: &impl SessionRepository,
+        clock: &dyn Clock,
+    ) -> anyhow::Result<()> {
+        if self.timer_started {
+            return Ok(());
+        }
+
+        let session_id = self
+            .session_id
+            .ok_or_else(|| anyhow::anyhow!("No session ID available"))?;
+        self.run_id = Some(repo.start_timer_run(session_id, clock.now()).await?);
+        self.timer_started = true;
+        repo.save_timer_started(true).await?;
+
+        Ok(())
+    }
+
+    /// Starts the timer at launch if it was running when the app last ran,
+    /// or if the settings ask for it. Needs the session to be initialized.
+    pub async fn restore_timer(
+        &mut self,
+        repo: &impl SessionRepository,
+        settings: &AppSettings,
+        clock: &dyn Clock,
+    ) -> anyhow::Result<()> {
+        let was_started = repo.saved_timer_started().await?;
+
+        let start = match settings.timer_auto_start {
+            TimerAutoStart::Restore => was_started,
+            TimerAutoStart::Launch => true,
+            TimerAutoStart::WorkingHours => {
+                was_started || settings.is_working_hours(clock.local_now().time())
+            }
+        };
+
+        if start {
+            self.start_timer(repo, clock).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stops the timer and closes the current run
+    pub async fn stop_timer(
+        &mut self,
+        repo: &impl SessionRepository,
+        clock: &dyn Clock,
+    ) -> anyhow::Result<()> {
+        if !self.timer_started {
+            return Ok(());
+        }
+
+        if let Some(run_id) = self.run_id {
+            repo.stop_timer_run(run_id, clock.now()).await?;
+        }
+        self.run_id = None;
+        self.timer_started = false;
+        repo.save_timer_started(false).await?;
+
+        Ok(())
+    }
+
+    pub async fn init_session(&mut self, repo: &impl SessionRepository) -> anyhow::Result<()> {
+        let session_uuid = Uuid::new_v4().to_string();
+
+        let session_id = repo
+            .insert_session(&session_uuid, self.session_start)
+            .await?;
+
+        self.session_id = Some(session_id);
+
+        Ok(())
+    }
+}
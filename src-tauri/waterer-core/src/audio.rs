@@ -0,0 +1,315 @@
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{notifier::NotificationKind, settings::config_dir};
+
+/// Sounds shipped with the app, by name
+const BUNDLED_SOUNDS: &[(&str, &[u8])] = &[
+    ("chime", include_bytes!("../sounds/chime.wav")),
+    ("drop", include_bytes!("../sounds/drop.wav")),
+    ("bell", include_bytes!("../sounds/bell.wav")),
+];
+
+/// Played by the sound channel when no sound is set for the notification
+pub const DEFAULT_SOUND: &str = "chime";
+
+/// File types the players can decode
+const SUPPORTED_EXTENSIONS: &[&str] = &["wav", "ogg"];
+
+/// A sound to play, either shipped with the app or a file of the user's
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Sound {
+    Bundled { name: String },
+    File { path: PathBuf },
+}
+
+impl Sound {
+    pub fn bundled(name: &str) -> Self {
+        Sound::Bundled {
+            name: name.to_string(),
+        }
+    }
+
+    /// Fails on unknown bundled sounds and on missing or unsupported files
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Sound::Bundled { name } => {
+                if !BUNDLED_SOUNDS.iter().any(|(bundled, _)| bundled == name) {
+                    return Err(anyhow::anyhow!("There is no bundled sound {}", name));
+                }
+            }
+            Sound::File { path } => {
+                let supported = path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| {
+                        SUPPORTED_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+                    });
+                if !supported {
+                    return Err(anyhow::anyhow!(
+                        "{} is not a WAV or OGG file",
+                        path.display()
+                    ));
+                }
+                if !path.is_file() {
+                    return Err(anyhow::anyhow!("{} does not exist", path.display()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [`Sound::validate`], but files also have to be inside
+    /// `sounds_dir`, so a caller can't have any file on disk read
+    pub fn validate_in(&self, sounds_dir: &Path) -> anyhow::Result<()> {
+        self.validate()?;
+
+        if let Sound::File { path } = self {
+            let inside = sounds_dir
+                .canonicalize()
+                .is_ok_and(|dir| path.canonicalize().is_ok_and(|path| path.starts_with(dir)));
+            if !inside {
+                return Err(anyhow::anyhow!(
+                    "{} is not in the sounds folder {}",
+                    path.display(),
+                    sounds_dir.display()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn load(&self) -> anyhow::Result<SoundClip> {
+        match self {
+            Sound::Bundled { name } => BUNDLED_SOUNDS
+                .iter()
+                .find(|(bundled, _)| bundled == name)
+                .map(|(name, data)| SoundClip {
+                    name: name.to_string(),
+                    data: Cow::Borrowed(data),
+                })
+                .ok_or_else(|| anyhow::anyhow!("There is no bundled sound {}", name)),
+            Sound::File { path } => Ok(SoundClip {
+                name: path.display().to_string(),
+                data: Cow::Owned(fs::read(path)?),
+            }),
+        }
+    }
+}
+
+/// Directory the user's own sound files are played from
+pub fn sounds_dir() -> PathBuf {
+    config_dir().join("sounds")
+}
+
+/// Names of the sounds shipped with the app
+pub fn bundled_sounds() -> Vec<&'static str> {
+    BUNDLED_SOUNDS.iter().map(|(name, _)| *name).collect()
+}
+
+/// The encoded contents of a sound, ready for a player
+#[derive(Debug, Clone)]
+pub struct SoundClip {
+    pub name: String,
+    pub data: Cow<'static, [u8]>,
+}
+
+/// Sounds for each kind of notification, by escalation step. The first
+/// sound plays for the first reminder since the latest sip, the second for
+/// the one after, and the last keeps playing from then on. An empty list
+/// plays nothing.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReminderSounds {
+    pub sip: Vec<Sound>,
+    pub welcome_back: Vec<Sound>,
+    pub achievement: Vec<Sound>,
+    pub goal: Vec<Sound>,
+}
+
+impl ReminderSounds {
+    /// The sound for escalation `step`, starting at 1
+    pub fn for_step(&self, kind: NotificationKind, step: u32) -> Option<&Sound> {
+        let sounds = match kind {
+            NotificationKind::Sip => &self.sip,
+            NotificationKind::WelcomeBack => &self.welcome_back,
+            NotificationKind::Achievement => &self.achievement,
+            NotificationKind::Goal => &self.goal,
+        };
+
+        let index = step.saturating_sub(1) as usize;
+        sounds.get(index).or(sounds.last())
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        [&self.sip, &self.welcome_back, &self.achievement, &self.goal]
+            .into_iter()
+            .flatten()
+            .try_for_each(Sound::validate)
+    }
+}
+
+/// Plays sounds on some output
+pub trait AudioSink: Send + Sync {
+    /// Starts playing `clip` at `volume` between 0.0 and 1.0 without waiting
+    /// for it to finish
+    fn play(&self, clip: SoundClip, volume: f32) -> anyhow::Result<()>;
+}
+
+/// Plays nothing and keeps what it was asked to play, for machines without
+/// audio and for checking alerts
+#[derive(Debug, Default, Clone)]
+pub struct NullAudioSink {
+    played: Arc<Mutex<Vec<(String, f32)>>>,
+}
+
+impl NullAudioSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name and volume of every clip played so far, oldest first
+    pub fn played(&self) -> Vec<(String, f32)> {
+        self.played
+            .lock()
+            .map(|played| played.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl AudioSink for NullAudioSink {
+    fn play(&self, clip: SoundClip, volume: f32) -> anyhow::Result<()> {
+        self.played
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Played sounds are poisoned"))?
+            .push((clip.name, volume));
+        Ok(())
+    }
+}
+
+/// Picks the sound for a notification and plays it at the configured volume
+#[derive(Clone)]
+pub struct AudioAlerts {
+    sink: Arc<dyn AudioSink>,
+    sounds: ReminderSounds,
+    volume_percent: u8,
+}
+
+impl AudioAlerts {
+    pub fn new(sink: Arc<dyn AudioSink>, sounds: ReminderSounds, volume_percent: u8) -> Self {
+        Self {
+            sink,
+            sounds,
+            volume_percent,
+        }
+    }
+
+    /// Plays the sound set for `kind` at escalation `step`. Returns whether
+    /// one is set.
+    pub fn alert(&self, kind: NotificationKind, step: u32) -> anyhow::Result<bool> {
+        let Some(sound) = self.sounds.for_step(kind, step) else {
+            return Ok(false);
+        };
+
+        self.play(sound)?;
+        Ok(true)
+    }
+
+    pub fn play(&self, sound: &Sound) -> anyhow::Result<()> {
+        let volume = f32::from(self.volume_percent.min(100)) / 100.0;
+        self.sink.play(sound.load()?, volume)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    /// Alerts with sip sounds by step on a sink that only records them
+    fn alerts(sip: &[&str], volume_percent: u8) -> (AudioAlerts, NullAudioSink) {
+        let sink = NullAudioSink::new();
+        let sounds = ReminderSounds {
+            sip: sip.iter().map(|name| Sound::bundled(name)).collect(),
+            ..ReminderSounds::default()
+        };
+        let alerts = AudioAlerts::new(Arc::new(sink.clone()), sounds, volume_percent);
+        (alerts, sink)
+    }
+
+    /// A fresh directory with one sound inside its `sounds` folder and one
+    /// next to it
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("waterer-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sounds")).unwrap();
+        fs::write(dir.join("sounds").join("mine.wav"), b"RIFF").unwrap();
+        fs::write(dir.join("elsewhere.wav"), b"RIFF").unwrap();
+        dir
+    }
+
+    #[test]
+    fn alerts_play_the_sound_for_the_step_and_repeat_the_last() {
+        let (alerts, sink) = alerts(&["drop", "bell"], 100);
+
+        for step in [1, 2, 3, 7] {
+            assert!(alerts.alert(NotificationKind::Sip, step).unwrap());
+        }
+
+        let played: Vec<String> = sink.played().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(played, vec!["drop", "bell", "bell", "bell"]);
+    }
+
+    #[test]
+    fn kinds_without_sounds_play_nothing() {
+        let (alerts, sink) = alerts(&["drop"], 100);
+
+        assert!(!alerts.alert(NotificationKind::Goal, 1).unwrap());
+        assert!(sink.played().is_empty());
+    }
+
+    #[test]
+    fn sounds_play_at_the_configured_volume() {
+        let (quiet, quiet_sink) = alerts(&["drop"], 40);
+        quiet.alert(NotificationKind::Sip, 1).unwrap();
+        assert_eq!(quiet_sink.played(), vec![("drop".to_string(), 0.4)]);
+
+        let (loud, loud_sink) = alerts(&["drop"], 250);
+        loud.alert(NotificationKind::Sip, 1).unwrap();
+        assert_eq!(loud_sink.played(), vec![("drop".to_string(), 1.0)]);
+    }
+
+    #[test]
+    fn only_files_in_the_sounds_folder_pass() {
+        let dir = scratch_dir("sounds-folder");
+        let sounds_dir = dir.join("sounds");
+        let file = |path: PathBuf| Sound::File { path };
+
+        assert!(Sound::bundled("bell").validate_in(&sounds_dir).is_ok());
+        assert!(file(sounds_dir.join("mine.wav"))
+            .validate_in(&sounds_dir)
+            .is_ok());
+        assert!(file(dir.join("elsewhere.wav"))
+            .validate_in(&sounds_dir)
+            .is_err());
+        assert!(file(sounds_dir.join("..").join("elsewhere.wav"))
+            .validate_in(&sounds_dir)
+            .is_err());
+        // Without the folder nothing is in it
+        assert!(file(sounds_dir.join("mine.wav"))
+            .validate_in(&dir.join("missing"))
+            .is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
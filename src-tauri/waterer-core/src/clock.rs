@@ -0,0 +1,233 @@
+use std::{fmt, sync::Mutex};
+
+use chrono::{
+    DateTime, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    TimeDelta, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+
+use crate::IgnorePoisoned;
+
+/// Source of the current time for everything that depends on it.
+///
+/// The app runs on [`SystemClock`]; [`ManualClock`] lets callers drive time
+/// by hand, e.g. across midnight or a DST switch.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// The time zone days start and end in
+    fn zone(&self) -> LocalZone {
+        LocalZone::System
+    }
+
+    fn local_now(&self) -> DateTime<LocalZone> {
+        self.now().with_timezone(&self.zone())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+    zone: LocalZone,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+            zone: LocalZone::System,
+        }
+    }
+
+    /// Counts days in `zone` instead of the system's time zone
+    pub fn in_zone(mut self, zone: impl Into<LocalZone>) -> Self {
+        self.zone = zone.into();
+        self
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().ignore_poisoned() = now;
+    }
+
+    pub fn advance(&self, by: TimeDelta) {
+        let mut now = self.now.lock().ignore_poisoned();
+        *now += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().ignore_poisoned()
+    }
+
+    fn zone(&self) -> LocalZone {
+        self.zone
+    }
+}
+
+/// The time zone of the user's day: the system's, or a named one
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LocalZone {
+    #[default]
+    System,
+    Named(Tz),
+}
+
+impl From<Tz> for LocalZone {
+    fn from(tz: Tz) -> Self {
+        LocalZone::Named(tz)
+    }
+}
+
+/// The UTC offset of a [`LocalZone`] at some instant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalOffset {
+    zone: LocalZone,
+    offset: FixedOffset,
+}
+
+impl Offset for LocalOffset {
+    fn fix(&self) -> FixedOffset {
+        self.offset
+    }
+}
+
+impl fmt::Display for LocalOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.offset.fmt(f)
+    }
+}
+
+impl LocalZone {
+    fn offset<O: Offset>(&self, offset: LocalResult<O>) -> LocalResult<LocalOffset> {
+        offset.map(|offset| LocalOffset {
+            zone: *self,
+            offset: offset.fix(),
+        })
+    }
+}
+
+impl TimeZone for LocalZone {
+    type Offset = LocalOffset;
+
+    fn from_offset(offset: &LocalOffset) -> Self {
+        offset.zone
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<LocalOffset> {
+        self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<LocalOffset> {
+        match self {
+            LocalZone::System => self.offset(Local.offset_from_local_datetime(local)),
+            LocalZone::Named(tz) => self.offset(tz.offset_from_local_datetime(local)),
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> LocalOffset {
+        self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> LocalOffset {
+        let offset = match self {
+            LocalZone::System => Local.offset_from_utc_datetime(utc).fix(),
+            LocalZone::Named(tz) => tz.offset_from_utc_datetime(utc).fix(),
+        };
+
+        LocalOffset {
+            zone: *self,
+            offset,
+        }
+    }
+}
+
+/// Resolves a wall clock time on the given day, picking the earlier instant
+/// when DST makes it ambiguous.
+pub fn at_local<Z: TimeZone>(zone: &Z, date: NaiveDate, time: NaiveTime) -> Option<DateTime<Z>> {
+    zone.from_local_datetime(&date.and_time(time)).earliest()
+}
+
+/// Local midnight, or the first valid hour if DST skips midnight
+pub fn start_of_day<Z: TimeZone>(zone: &Z, date: NaiveDate) -> DateTime<Z> {
+    at_local(zone, date, NaiveTime::MIN)
+        .or_else(|| at_local(zone, date, NaiveTime::from_hms_opt(1, 0, 0).unwrap()))
+        .expect("no valid local time at the start of the day")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, Timelike};
+    use chrono_tz::{America::Sao_Paulo, Europe::Berlin};
+
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn local_day_turns_over_at_local_midnight() {
+        let clock = ManualClock::new(utc("2025-01-10T22:59:59Z")).in_zone(Berlin);
+        assert_eq!(clock.local_now().date_naive(), date("2025-01-10"));
+
+        clock.advance(TimeDelta::seconds(1));
+        assert_eq!(clock.local_now().date_naive(), date("2025-01-11"));
+        assert_eq!(clock.now().date_naive(), date("2025-01-10"));
+    }
+
+    #[test]
+    fn local_now_follows_dst() {
+        let clock = ManualClock::new(utc("2025-03-30T00:59:00Z")).in_zone(Berlin);
+        assert_eq!(clock.local_now().hour(), 1);
+
+        clock.advance(TimeDelta::minutes(1));
+        assert_eq!(clock.local_now().hour(), 3);
+    }
+
+    #[test]
+    fn days_are_shorter_or_longer_across_dst() {
+        let zone = LocalZone::Named(Berlin);
+        let day_length = |day: &str| {
+            let day = date(day);
+            start_of_day(&zone, day + Days::new(1)) - start_of_day(&zone, day)
+        };
+
+        assert_eq!(day_length("2025-03-29"), TimeDelta::hours(24));
+        assert_eq!(day_length("2025-03-30"), TimeDelta::hours(23));
+        assert_eq!(day_length("2025-10-26"), TimeDelta::hours(25));
+    }
+
+    #[test]
+    fn start_of_day_skips_a_missing_midnight() {
+        // Brazil started DST at midnight, so 2018-11-04 began at 01:00
+        let start = start_of_day(&LocalZone::Named(Sao_Paulo), date("2018-11-04"));
+
+        assert_eq!(start.time(), NaiveTime::from_hms_opt(1, 0, 0).unwrap());
+        assert_eq!(start.with_timezone(&Utc), utc("2018-11-04T03:00:00Z"));
+    }
+
+    #[test]
+    fn at_local_picks_the_earlier_of_a_repeated_hour() {
+        let time = NaiveTime::from_hms_opt(2, 30, 0).unwrap();
+        let zone = LocalZone::Named(Berlin);
+
+        let repeated = at_local(&zone, date("2025-10-26"), time).unwrap();
+        assert_eq!(repeated.with_timezone(&Utc), utc("2025-10-26T00:30:00Z"));
+        assert!(at_local(&zone, date("2025-03-30"), time).is_none());
+    }
+}
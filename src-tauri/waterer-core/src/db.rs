@@ -0,0 +1,93 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Days, NaiveDate, TimeZone, Utc};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Pool, Sqlite, SqlitePool,
+};
+
+const DB_NAME: &str = "waterer.db";
+/// Matches SQLite's `datetime('now')` so rows written either way sort together
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub struct Database {
+    pub pool: Pool<Sqlite>,
+}
+
+impl Database {
+    pub async fn new() -> Result<Database, sqlx::Error> {
+        let db_path = get_db_file_path();
+        let connection_options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal);
+
+        let pool = SqlitePool::connect_with(connection_options).await?;
+
+        // Run migrations regardless of whether the database is new
+        // SQLx will track which migrations have been run
+        sqlx::migrate!("./migrations").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+
+    /// A fresh, fully migrated database that lives only as long as the pool
+    pub async fn in_memory() -> Result<Database, sqlx::Error> {
+        // Every in-memory connection is its own database, so keep exactly one
+        // connection around for the whole lifetime of the pool
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(SqliteConnectOptions::new().in_memory(true))
+            .await?;
+
+        sqlx::migrate!("./migrations").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+pub fn format_timestamp(at: DateTime<Utc>) -> String {
+    at.format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// The wall clock time of `at` in its own zone, as stored in `created_at_local`
+pub fn format_local_timestamp<Tz: TimeZone>(at: &DateTime<Tz>) -> String {
+    at.naive_local().format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// Bounds of `created_at_local` covering the local days `from` to `to`, the
+/// first inclusive and the second exclusive. Comparing the raw text lets
+/// its index be used.
+pub fn local_day_range(from: NaiveDate, to: NaiveDate) -> (String, String) {
+    (from.to_string(), (to + Days::new(1)).to_string())
+}
+
+pub fn init_db() {
+    let db_file_path = get_db_file_path();
+
+    if !db_file_path.exists() {
+        create_db_file();
+    }
+}
+
+fn create_db_file() {
+    let db_file_path = get_db_file_path();
+
+    let db_directory = db_file_path.parent().unwrap();
+
+    if !db_directory.exists() {
+        fs::create_dir(db_directory).unwrap();
+    }
+    fs::File::create_new(db_file_path).unwrap();
+}
+
+fn get_db_file_path() -> PathBuf {
+    match dirs::config_local_dir() {
+        Some(dir) => dir.join("waterer").join(DB_NAME),
+        None => Path::new(".").join("waterer").join(DB_NAME).to_path_buf(),
+    }
+}
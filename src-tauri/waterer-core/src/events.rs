@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::sip::{Sip, SipState};
+
+pub const SIP_LOGGED: &str = "sip-logged";
+pub const SIP_UPDATED: &str = "sip-updated";
+pub const TOTALS_CHANGED: &str = "totals-changed";
+pub const REMINDER_FIRED: &str = "reminder-fired";
+
+/// Payload of the `reminder-fired` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderFired {
+    pub fired_at: DateTime<Utc>,
+    pub last_sip_at: Option<DateTime<Utc>>,
+}
+
+/// Everything the backend announces to the UI and the tray. Serializes to
+/// the bare payload, the event name comes from [`DomainEvent::name`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum DomainEvent {
+    /// A new sip was stored
+    SipLogged(Sip),
+    /// A stored sip changed, for example a duplicate merged into it
+    SipUpdated(Sip),
+    /// The running totals changed, through a sip or a new day
+    TotalsChanged(SipState),
+    /// A reminder to drink was shown
+    ReminderFired(ReminderFired),
+}
+
+impl DomainEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::SipLogged(_) => SIP_LOGGED,
+            DomainEvent::SipUpdated(_) => SIP_UPDATED,
+            DomainEvent::TotalsChanged(_) => TOTALS_CHANGED,
+            DomainEvent::ReminderFired(_) => REMINDER_FIRED,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::future::Future;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::{db::format_timestamp, message::MessageId, repository::SqliteRepository};
+
+/// Percentages of the daily goal announced before the goal itself
+pub const GOAL_MILESTONES: [u8; 3] = [25, 50, 75];
+
+pub const GOAL_REACHED_PERCENT: u8 = 100;
+
+/// Progress towards the daily goal, as sent with the `goal-reached` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GoalProgress {
+    pub date: NaiveDate,
+    pub total_ml: i64,
+    pub goal_ml: i64,
+    /// The milestone that was crossed, 100 for the goal itself
+    pub percent: u8,
+}
+
+impl GoalProgress {
+    /// The message announcing this progress
+    pub fn message(&self) -> MessageId {
+        if self.percent == GOAL_REACHED_PERCENT {
+            MessageId::GoalReached
+        } else {
+            MessageId::GoalMilestone
+        }
+    }
+}
+
+/// Storage for goal notifications that were already shown
+pub trait GoalNotificationRepository {
+    /// Records a notification, returns false if it was already recorded
+    fn record_goal_notification(
+        &self,
+        date: NaiveDate,
+        percent: u8,
+        notified_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+}
+
+impl GoalNotificationRepository for SqliteRepository {
+    async fn record_goal_notification(
+        &self,
+        date: NaiveDate,
+        percent: u8,
+        notified_at: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let notified_at = format_timestamp(notified_at);
+
+        let result = sqlx::query!(
+            "INSERT OR IGNORE INTO goal_notifications (date, percent, notified_at) VALUES (?, ?, ?)",
+            date,
+            percent,
+            notified_at
+        )
+        .execute(self.pool())
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Milestones, in ascending order, that `total_ml` has reached
+pub fn reached_milestones(total_ml: i64, goal_ml: i64, include_partial: bool) -> Vec<u8> {
+    if goal_ml <= 0 {
+        return Vec::new();
+    }
+
+    let partial: &[u8] = if include_partial {
+        &GOAL_MILESTONES
+    } else {
+        &[]
+    };
+
+    partial
+        .iter()
+        .copied()
+        .chain([GOAL_REACHED_PERCENT])
+        .filter(|percent| total_ml * 100 >= goal_ml * i64::from(*percent))
+        .collect()
+}
+
+/// Records every milestone reached on `date` and returns the highest one
+/// that had not been announced yet. Milestones skipped over by a single sip
+/// are recorded without being announced.
+pub async fn next_goal_notification(
+    repo: &impl GoalNotificationRepository,
+    date: NaiveDate,
+    total_ml: i64,
+    goal_ml: i64,
+    include_partial: bool,
+    now: DateTime<Utc>,
+) -> Result<Option<GoalProgress>, sqlx::Error> {
+    let mut newest = None;
+    for percent in reached_milestones(total_ml, goal_ml, include_partial) {
+        if repo.record_goal_notification(date, percent, now).await? {
+            newest = Some(percent);
+        }
+    }
+
+    Ok(newest.map(|percent| GoalProgress {
+        date,
+        total_ml,
+        goal_ml,
+        percent,
+    }))
+}
@@ -0,0 +1,121 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::{repository::SipRepository, settings::AppSettings};
+
+/// Length of the rolling window the hourly limit applies to
+pub const INTAKE_WINDOW_MINUTES: i64 = 60;
+
+/// Upper bounds on how much may be drunk before a sip needs confirmation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntakeLimits {
+    pub max_hourly_ml: i64,
+    pub max_daily_ml: i64,
+}
+
+/// Outcome of checking a sip against the intake limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntakeCheck {
+    WithinLimits,
+    /// The sip would push the last hour's intake over the limit
+    OverHourlyLimit {
+        window_ml: i64,
+        limit_ml: i64,
+    },
+    /// The sip would push today's total over the limit
+    OverDailyLimit {
+        total_ml: i64,
+        limit_ml: i64,
+    },
+    /// The previous sip was logged only moments ago
+    TooSoonAfterLast {
+        seconds_since_last: i64,
+        min_gap_seconds: i64,
+    },
+}
+
+impl IntakeCheck {
+    pub fn is_within_limits(&self) -> bool {
+        matches!(self, IntakeCheck::WithinLimits)
+    }
+
+    /// Explanation shown when asking the user to confirm the sip
+    pub fn warning(&self) -> Option<String> {
+        match self {
+            IntakeCheck::WithinLimits => None,
+            IntakeCheck::OverHourlyLimit {
+                window_ml,
+                limit_ml,
+            } => Some(format!(
+                "This sip brings the last hour to {}ml, above your {}ml hourly limit",
+                window_ml, limit_ml
+            )),
+            IntakeCheck::OverDailyLimit { total_ml, limit_ml } => Some(format!(
+                "This sip brings today to {}ml, above your {}ml daily limit",
+                total_ml, limit_ml
+            )),
+            IntakeCheck::TooSoonAfterLast {
+                seconds_since_last, ..
+            } => Some(format!(
+                "You already logged a sip {} seconds ago",
+                seconds_since_last
+            )),
+        }
+    }
+}
+
+impl IntakeLimits {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            max_hourly_ml: settings.max_hourly_intake_ml,
+            max_daily_ml: settings.max_daily_intake_ml,
+        }
+    }
+
+    /// Start of the rolling window that ends at `now`
+    pub fn window_start(now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::minutes(INTAKE_WINDOW_MINUTES)
+    }
+
+    /// Checks a sip of `amount_ml` given what was drunk in the last hour and
+    /// today so far. The daily limit is reported first as it is the stricter
+    /// warning.
+    pub fn check(&self, amount_ml: i64, window_ml: i64, today_ml: i64) -> IntakeCheck {
+        let total_ml = today_ml + amount_ml;
+        if total_ml > self.max_daily_ml {
+            return IntakeCheck::OverDailyLimit {
+                total_ml,
+                limit_ml: self.max_daily_ml,
+            };
+        }
+
+        let window_ml = window_ml + amount_ml;
+        if window_ml > self.max_hourly_ml {
+            return IntakeCheck::OverHourlyLimit {
+                window_ml,
+                limit_ml: self.max_hourly_ml,
+            };
+        }
+
+        IntakeCheck::WithinLimits
+    }
+
+    /// Whether the last hour's intake has already reached the hourly limit
+    pub fn is_over_hourly(&self, window_ml: i64) -> bool {
+        window_ml >= self.max_hourly_ml
+    }
+}
+
+/// Checks a sip of `amount_ml` taken at `now` against the limits
+pub async fn check_intake(
+    repo: &impl SipRepository,
+    limits: &IntakeLimits,
+    amount_ml: i64,
+    today_ml: i64,
+    now: DateTime<Utc>,
+) -> Result<IntakeCheck, sqlx::Error> {
+    let window_ml = repo.amount_since(IntakeLimits::window_start(now)).await?;
+
+    Ok(limits.check(amount_ml, window_ml, today_ml))
+}
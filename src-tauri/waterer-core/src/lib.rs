@@ -0,0 +1,37 @@
+//! Domain logic shared by every waterer frontend: sip tracking, settings,
+//! the database layer and reminder scheduling. Nothing in here depends on
+//! Tauri, so it runs just as well in a CLI, a daemon or a plain `cargo test`.
+
+use std::sync::PoisonError;
+
+pub mod achievement;
+pub mod app_state;
+pub mod audio;
+pub mod clock;
+pub mod db;
+pub mod events;
+pub mod goal;
+pub mod intake;
+pub mod message;
+pub mod notifier;
+pub mod presence;
+pub mod reminder;
+pub mod repository;
+pub mod schedule;
+pub mod session;
+pub mod settings;
+pub mod sip;
+pub mod sip_query;
+pub mod state_actor;
+pub mod stats;
+pub mod streak;
+
+pub trait IgnorePoisoned<T> {
+    fn ignore_poisoned(self) -> T;
+}
+
+impl<T> IgnorePoisoned<T> for Result<T, PoisonError<T>> {
+    fn ignore_poisoned(self) -> T {
+        self.expect("poisoned")
+    }
+}
@@ -0,0 +1,510 @@
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::LocalZone,
+    notifier::{Notification, NotificationKind},
+    schedule::ReminderPolicy,
+    settings::AppSettings,
+    sip::SipState,
+};
+
+/// Message catalogs shipped with the app, by locale
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.toml")),
+    ("de", include_str!("../locales/de.toml")),
+    ("es", include_str!("../locales/es.toml")),
+    ("fr", include_str!("../locales/fr.toml")),
+    ("nl", include_str!("../locales/nl.toml")),
+];
+
+const FALLBACK_LOCALE: &str = "en";
+
+/// Placeholders every message can use
+const COMMON_PLACEHOLDERS: &[&str] = &[
+    "remaining_ml",
+    "percent",
+    "total_ml",
+    "goal_ml",
+    "streak",
+    "next_reminder",
+];
+
+/// The notification texts that can be translated and customised
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageId {
+    Sip,
+    WelcomeBack,
+    Achievement,
+    GoalReached,
+    GoalMilestone,
+}
+
+impl MessageId {
+    pub const ALL: [MessageId; 5] = [
+        MessageId::Sip,
+        MessageId::WelcomeBack,
+        MessageId::Achievement,
+        MessageId::GoalReached,
+        MessageId::GoalMilestone,
+    ];
+
+    pub fn kind(&self) -> NotificationKind {
+        match self {
+            MessageId::Sip => NotificationKind::Sip,
+            MessageId::WelcomeBack => NotificationKind::WelcomeBack,
+            MessageId::Achievement => NotificationKind::Achievement,
+            MessageId::GoalReached | MessageId::GoalMilestone => NotificationKind::Goal,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageId::Sip => "sip",
+            MessageId::WelcomeBack => "welcome_back",
+            MessageId::Achievement => "achievement",
+            MessageId::GoalReached => "goal_reached",
+            MessageId::GoalMilestone => "goal_milestone",
+        }
+    }
+
+    /// Whether `name` can be used in this message
+    pub fn has_placeholder(&self, name: &str) -> bool {
+        let extra: &[&str] = match self {
+            MessageId::WelcomeBack => &["away_minutes"],
+            MessageId::Achievement => &["achievement", "description"],
+            _ => &[],
+        };
+
+        COMMON_PLACEHOLDERS.contains(&name) || extra.contains(&name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageTemplate {
+    pub title: String,
+    pub body: String,
+}
+
+impl MessageTemplate {
+    /// Fails on unbalanced braces or placeholders `id` doesn't have
+    pub fn validate(&self, id: MessageId) -> anyhow::Result<()> {
+        for (part, template) in [("title", &self.title), ("body", &self.body)] {
+            let segments = parse(template).map_err(|e| {
+                anyhow::anyhow!("The {} {} template is invalid: {}", id.as_str(), part, e)
+            })?;
+
+            for segment in segments {
+                if let Segment::Placeholder(name) = segment {
+                    if !id.has_placeholder(name) {
+                        return Err(anyhow::anyhow!(
+                            "The {} {} template has an unknown placeholder {{{}}}",
+                            id.as_str(),
+                            part,
+                            name
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Templates the user wrote, used instead of the catalog's
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MessageTemplates {
+    pub sip: Option<MessageTemplate>,
+    pub welcome_back: Option<MessageTemplate>,
+    pub achievement: Option<MessageTemplate>,
+    pub goal_reached: Option<MessageTemplate>,
+    pub goal_milestone: Option<MessageTemplate>,
+}
+
+impl MessageTemplates {
+    pub fn get(&self, id: MessageId) -> Option<&MessageTemplate> {
+        match id {
+            MessageId::Sip => self.sip.as_ref(),
+            MessageId::WelcomeBack => self.welcome_back.as_ref(),
+            MessageId::Achievement => self.achievement.as_ref(),
+            MessageId::GoalReached => self.goal_reached.as_ref(),
+            MessageId::GoalMilestone => self.goal_milestone.as_ref(),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for id in MessageId::ALL {
+            if let Some(template) = self.get(id) {
+                template.validate(id)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The notification texts of one locale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Catalog {
+    #[serde(skip_deserializing)]
+    pub locale: String,
+    /// The language's own name, for picking it in the settings
+    pub name: String,
+    /// Shown for `{next_reminder}` when the reminder is already due
+    pub now: String,
+    pub sip: MessageTemplate,
+    pub welcome_back: MessageTemplate,
+    pub achievement: MessageTemplate,
+    pub goal_reached: MessageTemplate,
+    pub goal_milestone: MessageTemplate,
+}
+
+impl Catalog {
+    /// The catalog best matching a locale like `de-AT` or `fr_FR.UTF-8`,
+    /// English if there is none
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = match_locale(locale).unwrap_or(FALLBACK_LOCALE);
+        let (_, source) = CATALOGS
+            .iter()
+            .find(|(code, _)| *code == locale)
+            .expect("the fallback locale is bundled");
+
+        let mut catalog: Catalog = toml::from_str(source).expect("bundled catalogs are valid");
+        catalog.locale = locale.to_string();
+        catalog
+    }
+
+    /// Every bundled catalog
+    pub fn all() -> Vec<Self> {
+        CATALOGS
+            .iter()
+            .map(|(code, _)| Self::for_locale(code))
+            .collect()
+    }
+
+    pub fn is_supported(locale: &str) -> bool {
+        match_locale(locale).is_some()
+    }
+
+    pub fn get(&self, id: MessageId) -> &MessageTemplate {
+        match id {
+            MessageId::Sip => &self.sip,
+            MessageId::WelcomeBack => &self.welcome_back,
+            MessageId::Achievement => &self.achievement,
+            MessageId::GoalReached => &self.goal_reached,
+            MessageId::GoalMilestone => &self.goal_milestone,
+        }
+    }
+}
+
+/// The bundled locale for `locale`, trying the full tag before the language
+fn match_locale(locale: &str) -> Option<&'static str> {
+    let tag = locale
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-")
+        .to_lowercase();
+    let language = tag.split('-').next().unwrap_or_default();
+
+    let bundled = |candidate: &str| {
+        CATALOGS
+            .iter()
+            .map(|(code, _)| *code)
+            .find(|code| *code == candidate)
+    };
+
+    bundled(&tag).or_else(|| bundled(language))
+}
+
+/// The values filled into the placeholders
+#[derive(Debug, Default, Clone)]
+pub struct MessageContext {
+    pub remaining_ml: i64,
+    pub percent: i64,
+    pub total_ml: i64,
+    pub goal_ml: i64,
+    pub streak_days: i64,
+    /// `None` when the next reminder is already due
+    pub next_reminder: Option<DateTime<LocalZone>>,
+    pub away_minutes: i64,
+    pub achievement: String,
+    pub description: String,
+}
+
+impl MessageContext {
+    pub fn new(
+        settings: &AppSettings,
+        sip_state: &SipState,
+        streak_days: i64,
+        now: DateTime<LocalZone>,
+    ) -> Self {
+        let total_ml = sip_state.total_amount_today;
+        let goal_ml = settings.daily_goal_ml;
+
+        // A reminder going out now is due, so look one further: the next
+        // reminder if the user drinks now
+        let policy = ReminderPolicy::from_settings(settings);
+        let last_sip = sip_state
+            .last_sip_at()
+            .map(|at| at.with_timezone(&now.timezone()));
+        let mut next_reminder = policy.next_reminder(last_sip, total_ml, now);
+        if next_reminder <= now {
+            next_reminder = policy.next_reminder(Some(now), total_ml + settings.sip_amount_ml, now);
+        }
+
+        Self {
+            remaining_ml: (goal_ml - total_ml).max(0),
+            percent: if goal_ml > 0 {
+                total_ml * 100 / goal_ml
+            } else {
+                0
+            },
+            total_ml,
+            goal_ml,
+            streak_days,
+            next_reminder: (next_reminder > now).then_some(next_reminder),
+            ..Self::default()
+        }
+    }
+
+    fn value(&self, name: &str, catalog: &Catalog) -> Option<String> {
+        let value = match name {
+            "remaining_ml" => self.remaining_ml.to_string(),
+            "percent" => self.percent.to_string(),
+            "total_ml" => self.total_ml.to_string(),
+            "goal_ml" => self.goal_ml.to_string(),
+            "streak" => self.streak_days.to_string(),
+            "next_reminder" => match self.next_reminder {
+                Some(at) => at.format("%H:%M").to_string(),
+                None => catalog.now.clone(),
+            },
+            "away_minutes" => self.away_minutes.to_string(),
+            "achievement" => self.achievement.clone(),
+            "description" => self.description.clone(),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+/// Turns message ids into notifications, preferring the user's templates
+/// over the catalog's
+#[derive(Debug, Clone)]
+pub struct Messages {
+    catalog: Catalog,
+    templates: MessageTemplates,
+}
+
+impl Messages {
+    pub fn new(catalog: Catalog, templates: MessageTemplates) -> Self {
+        Self { catalog, templates }
+    }
+
+    pub fn render(&self, id: MessageId, context: &MessageContext) -> Notification {
+        let template = self
+            .templates
+            .get(id)
+            .unwrap_or_else(|| self.catalog.get(id));
+
+        Notification::new(
+            id.kind(),
+            render(&template.title, context, &self.catalog),
+            render(&template.body, context, &self.catalog),
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Splits a template into text and placeholders. `{{` and `}}` stand for
+/// literal braces.
+fn parse(template: &str) -> anyhow::Result<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        if i > 0 {
+            segments.push(Segment::Text(&rest[..i]));
+        }
+        let brace = &rest[i..i + 1];
+        rest = &rest[i + 1..];
+
+        if let Some(after) = rest.strip_prefix(brace) {
+            segments.push(Segment::Text(brace));
+            rest = after;
+            continue;
+        }
+        if brace == "}" {
+            return Err(anyhow::anyhow!("unmatched }}"));
+        }
+
+        let end = rest
+            .find('}')
+            .ok_or_else(|| anyhow::anyhow!("unclosed {{"))?;
+        let name = &rest[..end];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow::anyhow!("{{{}}} is not a placeholder", name));
+        }
+        segments.push(Segment::Placeholder(name));
+        rest = &rest[end + 1..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+
+    Ok(segments)
+}
+
+/// Fills in a template, leaving it as written if it doesn't parse
+fn render(template: &str, context: &MessageContext, catalog: &Catalog) -> String {
+    let Ok(segments) = parse(template) else {
+        return template.to_string();
+    };
+
+    segments
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text.to_string(),
+            Segment::Placeholder(name) => context
+                .value(name, catalog)
+                .unwrap_or_else(|| format!("{{{}}}", name)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(title: &str, body: &str) -> MessageTemplate {
+        MessageTemplate {
+            title: title.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    fn context() -> MessageContext {
+        MessageContext {
+            total_ml: 750,
+            goal_ml: 2000,
+            percent: 37,
+            away_minutes: 45,
+            ..MessageContext::default()
+        }
+    }
+
+    #[test]
+    fn templates_split_into_text_and_placeholders() {
+        assert_eq!(
+            parse("{percent}% of {{goal}}").unwrap(),
+            vec![
+                Segment::Placeholder("percent"),
+                Segment::Text("% of "),
+                Segment::Text("{"),
+                Segment::Text("goal"),
+                Segment::Text("}"),
+            ]
+        );
+
+        for broken in ["{percent", "percent}", "{}", "{two words}", "{{percent}"] {
+            assert!(parse(broken).is_err(), "{} parsed", broken);
+        }
+    }
+
+    #[test]
+    fn rendering_fills_in_known_placeholders_only() {
+        let catalog = Catalog::for_locale("en");
+
+        assert_eq!(
+            render("{total_ml}ml of {goal_ml}ml", &context(), &catalog),
+            "750ml of 2000ml"
+        );
+        assert_eq!(
+            render("{unknown} {{}}", &context(), &catalog),
+            "{unknown} {}"
+        );
+        // A template that doesn't parse is shown as written
+        assert_eq!(render("{percent", &context(), &catalog), "{percent");
+        // A reminder that is already due reads as the catalog's word for now
+        assert_eq!(render("{next_reminder}", &context(), &catalog), "now");
+    }
+
+    #[test]
+    fn placeholders_are_checked_against_the_message() {
+        let away = template("Back", "Away for {away_minutes} minutes");
+        assert!(away.validate(MessageId::WelcomeBack).is_ok());
+
+        let error = away.validate(MessageId::Sip).unwrap_err().to_string();
+        assert!(
+            error.contains("unknown placeholder {away_minutes}"),
+            "{}",
+            error
+        );
+
+        let error = template("{percent", "")
+            .validate(MessageId::Sip)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("sip title template is invalid"), "{}", error);
+    }
+
+    #[test]
+    fn every_bundled_catalog_is_valid() {
+        let catalogs = Catalog::all();
+        assert_eq!(catalogs.len(), CATALOGS.len());
+
+        for catalog in catalogs {
+            assert!(!catalog.name.is_empty(), "{} has no name", catalog.locale);
+            assert!(!catalog.now.is_empty(), "{} has no now", catalog.locale);
+            for id in MessageId::ALL {
+                if let Err(e) = catalog.get(id).validate(id) {
+                    panic!("{}: {}", catalog.locale, e);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn locales_fall_back_to_their_language_then_english() {
+        assert_eq!(Catalog::for_locale("de").locale, "de");
+        assert_eq!(Catalog::for_locale("de-AT").locale, "de");
+        assert_eq!(Catalog::for_locale("fr_FR.UTF-8").locale, "fr");
+        assert_eq!(Catalog::for_locale("NL-be").locale, "nl");
+        assert_eq!(Catalog::for_locale("xx-YY").locale, "en");
+        assert_eq!(Catalog::for_locale("").locale, "en");
+
+        assert!(Catalog::is_supported("es-MX"));
+        assert!(!Catalog::is_supported("ja-JP"));
+    }
+
+    #[test]
+    fn user_templates_take_the_place_of_the_catalog() {
+        let messages = Messages::new(
+            Catalog::for_locale("en"),
+            MessageTemplates {
+                sip: Some(template("Drink!", "{percent}% done")),
+                ..MessageTemplates::default()
+            },
+        );
+
+        let sip = messages.render(MessageId::Sip, &context());
+        assert_eq!(
+            (sip.title.as_str(), sip.body.as_str()),
+            ("Drink!", "37% done")
+        );
+
+        let back = messages.render(MessageId::WelcomeBack, &context());
+        assert_eq!(back.kind, NotificationKind::WelcomeBack);
+        assert_eq!(back.body, "You were away for 45 minutes, time for a sip");
+    }
+}
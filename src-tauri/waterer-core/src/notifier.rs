@@ -0,0 +1,502 @@
+use std::{
+    process::Command,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::{AudioAlerts, Sound, DEFAULT_SOUND},
+    settings::AppSettings,
+};
+
+/// What a notification is about, each kind has its own channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A sip is due
+    Sip,
+    /// The user came back after being away and a sip is due
+    WelcomeBack,
+    Achievement,
+    /// The daily goal or one of its milestones was reached
+    Goal,
+}
+
+impl NotificationKind {
+    /// Whether the user is asked to drink, as opposed to being told about progress
+    pub fn is_reminder(&self) -> bool {
+        matches!(self, NotificationKind::Sip | NotificationKind::WelcomeBack)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Sip => "sip",
+            NotificationKind::WelcomeBack => "welcome_back",
+            NotificationKind::Achievement => "achievement",
+            NotificationKind::Goal => "goal",
+        }
+    }
+}
+
+/// A way of getting a notification to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    /// The desktop notification
+    Toast,
+    /// A short sound, without anything to read. Sounds set up for a kind of
+    /// notification also play alongside the other channels.
+    Sound,
+    /// Runs the configured command, e.g. `notify-send` or `espeak`
+    Command,
+    /// A banner inside the main window, only while it is visible
+    Overlay,
+}
+
+/// The channels tried for each kind of notification, in order. The first
+/// channel that delivers wins, an empty list silences the kind.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationRoutes {
+    pub sip: Vec<NotificationChannel>,
+    pub welcome_back: Vec<NotificationChannel>,
+    pub achievement: Vec<NotificationChannel>,
+    pub goal: Vec<NotificationChannel>,
+}
+
+impl Default for NotificationRoutes {
+    fn default() -> Self {
+        let toast_then_overlay = vec![NotificationChannel::Toast, NotificationChannel::Overlay];
+        Self {
+            sip: toast_then_overlay.clone(),
+            welcome_back: toast_then_overlay.clone(),
+            achievement: toast_then_overlay.clone(),
+            goal: toast_then_overlay,
+        }
+    }
+}
+
+impl NotificationRoutes {
+    pub fn route(&self, kind: NotificationKind) -> &[NotificationChannel] {
+        match kind {
+            NotificationKind::Sip => &self.sip,
+            NotificationKind::WelcomeBack => &self.welcome_back,
+            NotificationKind::Achievement => &self.achievement,
+            NotificationKind::Goal => &self.goal,
+        }
+    }
+
+    pub fn uses(&self, channel: NotificationChannel) -> bool {
+        [&self.sip, &self.welcome_back, &self.achievement, &self.goal]
+            .iter()
+            .any(|route| route.contains(&channel))
+    }
+
+    /// No channel is listed twice for the same kind
+    pub fn is_valid(&self) -> bool {
+        [&self.sip, &self.welcome_back, &self.achievement, &self.goal]
+            .iter()
+            .all(|route| {
+                route
+                    .iter()
+                    .enumerate()
+                    .all(|(i, channel)| !route[..i].contains(channel))
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    /// Which reminder in a row since the latest sip this is, starting at 1
+    pub step: u32,
+}
+
+impl Notification {
+    pub fn new(kind: NotificationKind, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            kind,
+            title: title.into(),
+            body: body.into(),
+            step: 1,
+        }
+    }
+
+    pub fn with_step(mut self, step: u32) -> Self {
+        self.step = step.max(1);
+        self
+    }
+}
+
+/// Delivers notifications over one channel
+pub trait Notifier: Send + Sync {
+    fn channel(&self) -> NotificationChannel;
+
+    /// Fails if the notification could not be handed over, so the next
+    /// channel of the route gets a go
+    fn notify(&self, notification: &Notification) -> anyhow::Result<()>;
+}
+
+/// The notifiers available for delivery
+#[derive(Default)]
+pub struct Notifiers {
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a notifier, replacing any earlier one for the same channel
+    pub fn with(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.notifiers
+            .retain(|existing| existing.channel() != notifier.channel());
+        self.notifiers.push(Box::new(notifier));
+        self
+    }
+
+    /// Tries the channels of `route` in order until one delivers. Returns the
+    /// channel used, or `None` if the route is empty.
+    pub fn notify(
+        &self,
+        notification: &Notification,
+        route: &[NotificationChannel],
+    ) -> anyhow::Result<Option<NotificationChannel>> {
+        let mut errors = Vec::new();
+
+        for &channel in route {
+            let Some(notifier) = self.notifiers.iter().find(|n| n.channel() == channel) else {
+                errors.push(format!("{:?}: not available", channel));
+                continue;
+            };
+
+            match notifier.notify(notification) {
+                Ok(()) => return Ok(Some(channel)),
+                Err(e) => errors.push(format!("{:?}: {}", channel, e)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(None)
+        } else {
+            Err(anyhow::anyhow!(
+                "No channel delivered the notification ({})",
+                errors.join(", ")
+            ))
+        }
+    }
+
+    /// Sends `notification` down the route set for its kind. Sounds set up
+    /// for the kind accompany whatever channel delivered it.
+    pub fn send(
+        &self,
+        notification: &Notification,
+        routes: &NotificationRoutes,
+        alerts: &AudioAlerts,
+    ) -> anyhow::Result<Option<NotificationChannel>> {
+        let delivered = self.notify(notification, routes.route(notification.kind))?;
+
+        // The sound channel already played it
+        if delivered.is_some_and(|channel| channel != NotificationChannel::Sound) {
+            if let Err(e) = alerts.alert(notification.kind, notification.step) {
+                eprintln!("Failed to play notification sound: {}", e);
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    /// Sends `notification` down the routes in `settings`, or nowhere while
+    /// notifications are turned off
+    pub fn deliver(
+        &self,
+        notification: &Notification,
+        settings: &AppSettings,
+        alerts: &AudioAlerts,
+    ) -> anyhow::Result<Option<NotificationChannel>> {
+        if !settings.notifications_enabled {
+            return Ok(None);
+        }
+
+        self.send(notification, &settings.notification_routes, alerts)
+    }
+}
+
+/// Runs a program for every notification. `{title}`, `{body}` and `{kind}`
+/// in the arguments are replaced with the notification's.
+pub struct CommandNotifier {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandNotifier {
+    /// Takes the program followed by its arguments, `None` if empty
+    pub fn from_command(command: &[String]) -> Option<Self> {
+        let (program, args) = command.split_first()?;
+        Some(Self {
+            program: program.clone(),
+            args: args.to_vec(),
+        })
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn channel(&self) -> NotificationChannel {
+        NotificationChannel::Command
+    }
+
+    fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        let args = self.args.iter().map(|arg| {
+            arg.replace("{title}", &notification.title)
+                .replace("{body}", &notification.body)
+                .replace("{kind}", notification.kind.as_str())
+        });
+        let mut child = Command::new(&self.program).args(args).spawn()?;
+
+        // Wait in the background so the command can't hold up reminders
+        let program = self.program.clone();
+        thread::spawn(move || match child.wait() {
+            Ok(status) if !status.success() => {
+                eprintln!("Notification command {} exited with {}", program, status)
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to wait for notification command {}: {}", program, e),
+        });
+
+        Ok(())
+    }
+}
+
+/// Plays the sound set for the notification's kind and escalation step, or
+/// the default one if there is none
+pub struct SoundNotifier {
+    alerts: AudioAlerts,
+}
+
+impl SoundNotifier {
+    pub fn new(alerts: AudioAlerts) -> Self {
+        Self { alerts }
+    }
+}
+
+impl Notifier for SoundNotifier {
+    fn channel(&self) -> NotificationChannel {
+        NotificationChannel::Sound
+    }
+
+    fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        if !self.alerts.alert(notification.kind, notification.step)? {
+            self.alerts.play(&Sound::bundled(DEFAULT_SOUND))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps every notification instead of showing it, so reminder logic can be
+/// checked without a desktop
+#[derive(Clone)]
+pub struct RecordingNotifier {
+    channel: NotificationChannel,
+    sent: Arc<Mutex<Vec<Notification>>>,
+    fail: bool,
+}
+
+impl RecordingNotifier {
+    pub fn new(channel: NotificationChannel) -> Self {
+        Self {
+            channel,
+            sent: Arc::default(),
+            fail: false,
+        }
+    }
+
+    /// A notifier that refuses everything, to exercise the fallback
+    pub fn failing(channel: NotificationChannel) -> Self {
+        Self {
+            fail: true,
+            ..Self::new(channel)
+        }
+    }
+
+    /// The notifications delivered so far, oldest first
+    pub fn sent(&self) -> Vec<Notification> {
+        self.sent
+            .lock()
+            .map(|sent| sent.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Notifier for RecordingNotifier {
+    fn channel(&self) -> NotificationChannel {
+        self.channel
+    }
+
+    fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        if self.fail {
+            return Err(anyhow::anyhow!("{:?} is set up to fail", self.channel));
+        }
+
+        self.sent
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Recorded notifications are poisoned"))?
+            .push(notification.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{NullAudioSink, ReminderSounds};
+
+    use NotificationChannel::{Command, Overlay, Sound as SoundChannel, Toast};
+
+    fn reminder() -> Notification {
+        Notification::new(NotificationKind::Sip, "Time for a sip", "Drink up")
+    }
+
+    /// Alerts that play `sip_sounds` for sip reminders on a sink that only
+    /// records them
+    fn alerts(sip_sounds: &[&str]) -> (AudioAlerts, NullAudioSink) {
+        let sink = NullAudioSink::new();
+        let sounds = ReminderSounds {
+            sip: sip_sounds.iter().map(|name| Sound::bundled(name)).collect(),
+            ..ReminderSounds::default()
+        };
+        (AudioAlerts::new(Arc::new(sink.clone()), sounds, 100), sink)
+    }
+
+    fn played(sink: &NullAudioSink) -> Vec<String> {
+        sink.played().into_iter().map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn the_first_channel_of_the_route_delivers() {
+        let toast = RecordingNotifier::new(Toast);
+        let overlay = RecordingNotifier::new(Overlay);
+        let notifiers = Notifiers::new().with(toast.clone()).with(overlay.clone());
+        let (alerts, _) = alerts(&[]);
+
+        let delivered = notifiers
+            .send(&reminder(), &NotificationRoutes::default(), &alerts)
+            .unwrap();
+
+        assert_eq!(delivered, Some(Toast));
+        assert_eq!(toast.sent(), vec![reminder()]);
+        assert!(overlay.sent().is_empty());
+    }
+
+    #[test]
+    fn a_failing_channel_falls_back_to_the_next() {
+        let overlay = RecordingNotifier::new(Overlay);
+        let notifiers = Notifiers::new()
+            .with(RecordingNotifier::failing(Toast))
+            .with(overlay.clone());
+        let (alerts, _) = alerts(&[]);
+
+        let delivered = notifiers
+            .send(&reminder(), &NotificationRoutes::default(), &alerts)
+            .unwrap();
+
+        assert_eq!(delivered, Some(Overlay));
+        assert_eq!(overlay.sent(), vec![reminder()]);
+    }
+
+    #[test]
+    fn each_kind_takes_its_own_route() {
+        let toast = RecordingNotifier::new(Toast);
+        let command = RecordingNotifier::new(Command);
+        let notifiers = Notifiers::new().with(toast.clone()).with(command.clone());
+        let routes = NotificationRoutes {
+            achievement: vec![Command, Toast],
+            goal: vec![],
+            ..NotificationRoutes::default()
+        };
+        let (alerts, _) = alerts(&[]);
+
+        let achievement = Notification::new(NotificationKind::Achievement, "Unlocked", "First sip");
+        let goal = Notification::new(NotificationKind::Goal, "Goal reached", "2000 ml");
+        notifiers.send(&reminder(), &routes, &alerts).unwrap();
+        notifiers.send(&achievement, &routes, &alerts).unwrap();
+        // An empty route silences the kind
+        assert_eq!(notifiers.send(&goal, &routes, &alerts).unwrap(), None);
+
+        assert_eq!(toast.sent(), vec![reminder()]);
+        assert_eq!(command.sent(), vec![achievement]);
+    }
+
+    #[test]
+    fn no_channel_delivering_is_an_error_naming_each() {
+        let notifiers = Notifiers::new().with(RecordingNotifier::failing(Toast));
+        let (alerts, sink) = alerts(&["drop"]);
+
+        let error = notifiers
+            .send(&reminder(), &NotificationRoutes::default(), &alerts)
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            error.contains("Toast: Toast is set up to fail"),
+            "{}",
+            error
+        );
+        assert!(error.contains("Overlay: not available"), "{}", error);
+        assert!(sink.played().is_empty());
+    }
+
+    #[test]
+    fn the_sound_for_the_step_accompanies_other_channels() {
+        let toast = RecordingNotifier::new(Toast);
+        let notifiers = Notifiers::new().with(toast.clone());
+        let (alerts, sink) = alerts(&["drop", "bell"]);
+
+        for step in 1..=3 {
+            notifiers
+                .send(
+                    &reminder().with_step(step),
+                    &NotificationRoutes::default(),
+                    &alerts,
+                )
+                .unwrap();
+        }
+
+        assert_eq!(toast.sent().len(), 3);
+        assert_eq!(played(&sink), vec!["drop", "bell", "bell"]);
+    }
+
+    #[test]
+    fn the_sound_channel_plays_its_sound_only_once() {
+        let (alerts, sink) = alerts(&["drop"]);
+        let notifiers = Notifiers::new().with(SoundNotifier::new(alerts.clone()));
+        let routes = NotificationRoutes {
+            sip: vec![SoundChannel, Toast],
+            ..NotificationRoutes::default()
+        };
+
+        let delivered = notifiers.send(&reminder(), &routes, &alerts).unwrap();
+
+        assert_eq!(delivered, Some(SoundChannel));
+        assert_eq!(played(&sink), vec!["drop"]);
+    }
+
+    #[test]
+    fn the_sound_channel_falls_back_to_the_default_sound() {
+        let (alerts, sink) = alerts(&[]);
+        let notifiers = Notifiers::new().with(SoundNotifier::new(alerts.clone()));
+        let routes = NotificationRoutes {
+            sip: vec![SoundChannel],
+            ..NotificationRoutes::default()
+        };
+
+        notifiers.send(&reminder(), &routes, &alerts).unwrap();
+
+        assert_eq!(played(&sink), vec![DEFAULT_SOUND]);
+    }
+}
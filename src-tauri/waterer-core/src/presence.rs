@@ -0,0 +1,192 @@
+use std::future::Future;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use tokio::sync::watch;
+
+/// Whether someone is at the desk, as reported by the desktop session
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Presence {
+    #[default]
+    Active,
+    /// No input since `since`
+    Idle { since: DateTime<Utc> },
+    /// The screen is locked
+    Locked,
+}
+
+/// Source of presence changes. The app uses the desktop's session signals
+/// where it can and [`NoPresenceMonitor`] elsewhere.
+pub trait PresenceMonitor: Send + 'static {
+    /// Sends every change to `presence` until the monitor fails or nobody
+    /// listens anymore
+    fn run(
+        self,
+        presence: watch::Sender<Presence>,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+/// For platforms without presence detection, always reports [`Presence::Active`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoPresenceMonitor;
+
+impl PresenceMonitor for NoPresenceMonitor {
+    async fn run(self, presence: watch::Sender<Presence>) -> anyhow::Result<()> {
+        presence.send_replace(Presence::Active);
+        Ok(())
+    }
+}
+
+/// What changed since the previous [`AwayTracker::update`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AwayChange {
+    Unchanged,
+    WentAway,
+    CameBack { away_for: TimeDelta },
+}
+
+/// Decides when the user counts as away: while the screen is locked, or once
+/// idle for longer than the threshold
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AwayTracker {
+    away_since: Option<DateTime<Utc>>,
+}
+
+impl AwayTracker {
+    pub fn is_away(&self) -> bool {
+        self.away_since.is_some()
+    }
+
+    pub fn update(
+        &mut self,
+        presence: Presence,
+        idle_threshold: TimeDelta,
+        now: DateTime<Utc>,
+    ) -> AwayChange {
+        let away_since = match presence {
+            Presence::Active => None,
+            Presence::Idle { since } if now - since >= idle_threshold => Some(since),
+            Presence::Idle { .. } => None,
+            Presence::Locked => Some(self.away_since.unwrap_or(now)),
+        };
+
+        let change = match (self.away_since, away_since) {
+            (None, Some(_)) => AwayChange::WentAway,
+            (Some(since), None) => AwayChange::CameBack {
+                away_for: now - since,
+            },
+            _ => AwayChange::Unchanged,
+        };
+
+        // Keep the earliest start when going from idle to locked or back
+        self.away_since = match (self.away_since, away_since) {
+            (Some(before), Some(now_since)) => Some(before.min(now_since)),
+            (_, away_since) => away_since,
+        };
+
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: TimeDelta = TimeDelta::minutes(5);
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        "2025-06-10T08:00:00Z".parse::<DateTime<Utc>>().unwrap() + TimeDelta::minutes(minute)
+    }
+
+    #[test]
+    fn locking_goes_away_and_unlocking_comes_back() {
+        let mut tracker = AwayTracker::default();
+        assert_eq!(
+            tracker.update(Presence::Active, THRESHOLD, at(0)),
+            AwayChange::Unchanged
+        );
+
+        assert_eq!(
+            tracker.update(Presence::Locked, THRESHOLD, at(1)),
+            AwayChange::WentAway
+        );
+        assert!(tracker.is_away());
+        assert_eq!(
+            tracker.update(Presence::Locked, THRESHOLD, at(20)),
+            AwayChange::Unchanged
+        );
+
+        assert_eq!(
+            tracker.update(Presence::Active, THRESHOLD, at(31)),
+            AwayChange::CameBack {
+                away_for: TimeDelta::minutes(30)
+            }
+        );
+        assert!(!tracker.is_away());
+        assert_eq!(
+            tracker.update(Presence::Active, THRESHOLD, at(32)),
+            AwayChange::Unchanged
+        );
+    }
+
+    #[test]
+    fn idle_counts_as_away_only_past_the_threshold() {
+        let mut tracker = AwayTracker::default();
+        let idle = Presence::Idle { since: at(0) };
+
+        assert_eq!(
+            tracker.update(idle, THRESHOLD, at(4)),
+            AwayChange::Unchanged
+        );
+        assert!(!tracker.is_away());
+
+        assert_eq!(tracker.update(idle, THRESHOLD, at(5)), AwayChange::WentAway);
+        // Away from when the input stopped, not from when it was noticed
+        assert_eq!(
+            tracker.update(Presence::Active, THRESHOLD, at(12)),
+            AwayChange::CameBack {
+                away_for: TimeDelta::minutes(12)
+            }
+        );
+    }
+
+    #[test]
+    fn short_idle_spells_never_go_away() {
+        let mut tracker = AwayTracker::default();
+        for minute in 0..3 {
+            let idle = Presence::Idle { since: at(minute) };
+            assert_eq!(
+                tracker.update(idle, THRESHOLD, at(minute + 1)),
+                AwayChange::Unchanged
+            );
+            assert_eq!(
+                tracker.update(Presence::Active, THRESHOLD, at(minute + 2)),
+                AwayChange::Unchanged
+            );
+        }
+    }
+
+    #[test]
+    fn idle_then_locked_is_one_absence_from_the_earliest_start() {
+        let mut tracker = AwayTracker::default();
+
+        let idle = Presence::Idle { since: at(0) };
+        assert_eq!(tracker.update(idle, THRESHOLD, at(6)), AwayChange::WentAway);
+        assert_eq!(
+            tracker.update(Presence::Locked, THRESHOLD, at(10)),
+            AwayChange::Unchanged
+        );
+        assert_eq!(
+            tracker.update(idle, THRESHOLD, at(15)),
+            AwayChange::Unchanged
+        );
+
+        assert_eq!(
+            tracker.update(Presence::Active, THRESHOLD, at(40)),
+            AwayChange::CameBack {
+                away_for: TimeDelta::minutes(40)
+            }
+        );
+    }
+}
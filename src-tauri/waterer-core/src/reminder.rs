@@ -0,0 +1,128 @@
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::{db::format_timestamp, repository::SqliteRepository};
+
+/// How long the snooze action holds reminders
+pub const SNOOZE_MINUTES: i64 = 10;
+
+/// Action id notification servers report when a notification is closed
+/// without picking an action
+const CLOSED_ACTION_ID: &str = "__closed";
+
+/// What the user did with a reminder notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum ReminderAction {
+    /// Log a sip of the configured amount
+    LogSip,
+    /// Log a whole glass
+    LogGlass,
+    /// Hold reminders for [`SNOOZE_MINUTES`]
+    Snooze,
+    /// Closed without picking an action
+    Dismissed,
+}
+
+impl ReminderAction {
+    /// The buttons shown on a reminder, in order
+    pub const BUTTONS: [ReminderAction; 3] = [
+        ReminderAction::LogSip,
+        ReminderAction::LogGlass,
+        ReminderAction::Snooze,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReminderAction::LogSip => "log_sip",
+            ReminderAction::LogGlass => "log_glass",
+            ReminderAction::Snooze => "snooze",
+            ReminderAction::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            ReminderAction::LogSip => "Log sip".to_string(),
+            ReminderAction::LogGlass => "Log glass".to_string(),
+            ReminderAction::Snooze => format!("Snooze {}m", SNOOZE_MINUTES),
+            ReminderAction::Dismissed => "Dismiss".to_string(),
+        }
+    }
+
+    /// The action behind an id reported by the notification server
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "log_sip" => Some(ReminderAction::LogSip),
+            "log_glass" => Some(ReminderAction::LogGlass),
+            "snooze" => Some(ReminderAction::Snooze),
+            "dismissed" | CLOSED_ACTION_ID => Some(ReminderAction::Dismissed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromRow)]
+pub struct ReminderOutcome {
+    pub id: i64,
+    pub fired_at: DateTime<Utc>,
+    pub action: ReminderAction,
+    pub acted_at: DateTime<Utc>,
+    pub sip_id: Option<i64>,
+}
+
+/// Storage for what happened to reminder notifications
+pub trait ReminderOutcomeRepository {
+    fn record_reminder_outcome(
+        &self,
+        fired_at: DateTime<Utc>,
+        action: ReminderAction,
+        acted_at: DateTime<Utc>,
+        sip_id: Option<i64>,
+    ) -> impl Future<Output = Result<i64, sqlx::Error>> + Send;
+
+    /// The latest outcomes, newest first
+    fn reminder_outcomes(
+        &self,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<ReminderOutcome>, sqlx::Error>> + Send;
+}
+
+impl ReminderOutcomeRepository for SqliteRepository {
+    async fn record_reminder_outcome(
+        &self,
+        fired_at: DateTime<Utc>,
+        action: ReminderAction,
+        acted_at: DateTime<Utc>,
+        sip_id: Option<i64>,
+    ) -> Result<i64, sqlx::Error> {
+        let fired_at = format_timestamp(fired_at);
+        let action = action.as_str();
+        let acted_at = format_timestamp(acted_at);
+
+        let result = sqlx::query!(
+            "INSERT INTO reminder_outcomes (fired_at, action, acted_at, sip_id) VALUES (?, ?, ?, ?)",
+            fired_at,
+            action,
+            acted_at,
+            sip_id
+        )
+        .execute(self.pool())
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn reminder_outcomes(&self, limit: u32) -> Result<Vec<ReminderOutcome>, sqlx::Error> {
+        sqlx::query_as::<_, ReminderOutcome>(
+            "SELECT * FROM reminder_outcomes ORDER BY fired_at DESC, id DESC LIMIT ?",
+        )
+        .bind(limit)
+        .fetch_all(self.pool())
+        .await
+    }
+}
@@ -0,0 +1,748 @@
+use std::future::Future;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{prelude::FromRow, Pool, Sqlite};
+
+use crate::{
+    clock::LocalZone,
+    db::{format_local_timestamp, format_timestamp},
+    session::{SessionEndReason, SessionSummary, TimerRunSummary},
+    sip::{Sip, SipSource},
+};
+
+/// Number and volume of a set of sips
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromRow)]
+pub struct SipTotals {
+    pub count: i64,
+    pub amount: i64,
+}
+
+/// A row of the trigger-maintained `daily_totals` table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromRow)]
+pub struct DailyTotal {
+    /// Local calendar day
+    pub date: NaiveDate,
+    pub total_ml: i64,
+    pub sip_count: i64,
+    /// The daily goal in effect on that day
+    pub goal_ml: i64,
+    pub goal_met: bool,
+}
+
+/// Storage for individual sips
+pub trait SipRepository {
+    /// Stores a new sip and returns its id
+    fn insert_sip(
+        &self,
+        amount: i64,
+        session_id: i64,
+        run_id: Option<i64>,
+        created_at: DateTime<LocalZone>,
+        source: SipSource,
+        request_id: Option<&str>,
+    ) -> impl Future<Output = Result<i64, sqlx::Error>> + Send;
+
+    fn sip(&self, sip_id: i64) -> impl Future<Output = Result<Option<Sip>, sqlx::Error>> + Send;
+
+    /// The sip stored for a client request id, or the one it was merged into
+    fn sip_by_request_id(
+        &self,
+        request_id: &str,
+    ) -> impl Future<Output = Result<Option<Sip>, sqlx::Error>> + Send;
+
+    /// Adds `amount` to an existing sip instead of logging a new one. The
+    /// request id is kept so a retry can be recognised.
+    fn merge_into_sip(
+        &self,
+        sip_id: i64,
+        amount: i64,
+        request_id: Option<&str>,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Every sip ever logged, newest first
+    fn all_sips(&self) -> impl Future<Output = Result<Vec<Sip>, sqlx::Error>> + Send;
+
+    fn latest_sip(&self) -> impl Future<Output = Result<Option<Sip>, sqlx::Error>> + Send;
+
+    fn set_notified_user(
+        &self,
+        sip_id: i64,
+        notified_user: bool,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Volume of the sips logged at or after `since`
+    fn amount_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> impl Future<Output = Result<i64, sqlx::Error>> + Send;
+}
+
+/// Per-day totals, kept up to date by triggers on the `sips` table
+pub trait DailyTotalsRepository {
+    fn daily_total(
+        &self,
+        date: NaiveDate,
+    ) -> impl Future<Output = Result<Option<DailyTotal>, sqlx::Error>> + Send;
+
+    /// Days with sips between `from` and `to`, both inclusive, oldest first
+    fn daily_totals(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<DailyTotal>, sqlx::Error>> + Send;
+
+    fn all_time_totals(&self) -> impl Future<Output = Result<SipTotals, sqlx::Error>> + Send;
+
+    /// Records the goal for new days and applies it to `today` as well
+    fn set_daily_goal(
+        &self,
+        goal_ml: i64,
+        today: NaiveDate,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Days whose stored totals disagree with the raw sips
+    fn daily_totals_mismatches(
+        &self,
+    ) -> impl Future<Output = Result<Vec<NaiveDate>, sqlx::Error>> + Send;
+}
+
+/// Storage for app sessions
+pub trait SessionRepository {
+    /// Stores a new session and returns its row id
+    fn insert_session(
+        &self,
+        session_uuid: &str,
+        session_start: DateTime<Utc>,
+    ) -> impl Future<Output = Result<i64, sqlx::Error>> + Send;
+
+    /// Records that the session is still running at `at`
+    fn session_heartbeat(
+        &self,
+        id: i64,
+        at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Closes a running session, leaving one that already ended untouched
+    fn end_session(
+        &self,
+        id: i64,
+        session_end: DateTime<Utc>,
+        reason: SessionEndReason,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Closes every session left open by a crash at the last moment it was
+    /// known to be alive, returning how many were closed. Sessions from
+    /// before heartbeats end as unknown. Must run before the new session is
+    /// inserted.
+    fn recover_sessions(&self) -> impl Future<Output = Result<u64, sqlx::Error>> + Send;
+
+    /// Every session with its sip count and volume, newest first
+    fn session_summaries(
+        &self,
+    ) -> impl Future<Output = Result<Vec<SessionSummary>, sqlx::Error>> + Send;
+
+    /// Stores a new timer run of the session and returns its row id
+    fn start_timer_run(
+        &self,
+        session_id: i64,
+        started_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<i64, sqlx::Error>> + Send;
+
+    fn stop_timer_run(
+        &self,
+        run_id: i64,
+        stopped_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Whether the timer was running when it was last started or stopped
+    fn saved_timer_started(&self) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Remembers the timer state for the next launch
+    fn save_timer_started(
+        &self,
+        timer_started: bool,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Timer runs with their sip count and volume, newest first, limited to
+    /// one session if given
+    fn timer_run_summaries(
+        &self,
+        session_id: Option<i64>,
+    ) -> impl Future<Output = Result<Vec<TimerRunSummary>, sqlx::Error>> + Send;
+}
+
+/// Both repositories backed by a SQLite pool
+#[derive(Debug, Clone)]
+pub struct SqliteRepository {
+    pool: Pool<Sqlite>,
+}
+
+impl SqliteRepository {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &Pool<Sqlite> {
+        &self.pool
+    }
+}
+
+impl SipRepository for SqliteRepository {
+    async fn insert_sip(
+        &self,
+        amount: i64,
+        session_id: i64,
+        run_id: Option<i64>,
+        created_at: DateTime<LocalZone>,
+        source: SipSource,
+        request_id: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let created_at_local = format_local_timestamp(&created_at);
+        let created_at = format_timestamp(created_at.with_timezone(&Utc));
+        let source = source.as_str();
+
+        let result = sqlx::query!(
+            "INSERT INTO sips (amount, session_id, run_id, created_at, created_at_local, source, request_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            amount,
+            session_id,
+            run_id,
+            created_at,
+            created_at_local,
+            source,
+            request_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn sip(&self, sip_id: i64) -> Result<Option<Sip>, sqlx::Error> {
+        sqlx::query_as::<_, Sip>("SELECT * FROM sips WHERE id = ?")
+            .bind(sip_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn sip_by_request_id(&self, request_id: &str) -> Result<Option<Sip>, sqlx::Error> {
+        sqlx::query_as::<_, Sip>(
+            "SELECT * FROM sips
+             WHERE request_id = ?1
+                OR id = (SELECT sip_id FROM sip_requests WHERE request_id = ?1)",
+        )
+        .bind(request_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn merge_into_sip(
+        &self,
+        sip_id: i64,
+        amount: i64,
+        request_id: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        // The primary key turns a concurrent retry into an error instead of
+        // a second merge
+        if let Some(request_id) = request_id {
+            sqlx::query!(
+                "INSERT INTO sip_requests (request_id, sip_id) VALUES (?, ?)",
+                request_id,
+                sip_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "UPDATE sips SET amount = amount + ? WHERE id = ?",
+            amount,
+            sip_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    async fn all_sips(&self) -> Result<Vec<Sip>, sqlx::Error> {
+        sqlx::query_as::<_, Sip>("SELECT * FROM sips ORDER BY created_at DESC")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn latest_sip(&self) -> Result<Option<Sip>, sqlx::Error> {
+        sqlx::query_as::<_, Sip>("SELECT * FROM sips ORDER BY created_at DESC, id DESC LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn set_notified_user(&self, sip_id: i64, notified_user: bool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE sips SET notified_user = ? WHERE id = ?",
+            notified_user,
+            sip_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn amount_since(&self, since: DateTime<Utc>) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COALESCE(SUM(amount), 0) FROM sips WHERE created_at >= ?",
+        )
+        .bind(format_timestamp(since))
+        .fetch_one(&self.pool)
+        .await
+    }
+}
+
+impl DailyTotalsRepository for SqliteRepository {
+    async fn daily_total(&self, date: NaiveDate) -> Result<Option<DailyTotal>, sqlx::Error> {
+        sqlx::query_as::<_, DailyTotal>("SELECT * FROM daily_totals WHERE date = ?")
+            .bind(date)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn daily_totals(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyTotal>, sqlx::Error> {
+        sqlx::query_as::<_, DailyTotal>(
+            "SELECT * FROM daily_totals WHERE date BETWEEN ? AND ? ORDER BY date",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn all_time_totals(&self) -> Result<SipTotals, sqlx::Error> {
+        sqlx::query_as::<_, SipTotals>(
+            "SELECT COALESCE(SUM(sip_count), 0) AS count, COALESCE(SUM(total_ml), 0) AS amount
+             FROM daily_totals",
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn set_daily_goal(&self, goal_ml: i64, today: NaiveDate) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("UPDATE daily_goal SET goal_ml = ? WHERE id = 1", goal_ml)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query!(
+            "UPDATE daily_totals SET goal_ml = ? WHERE date = ?",
+            goal_ml,
+            today
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    async fn daily_totals_mismatches(&self) -> Result<Vec<NaiveDate>, sqlx::Error> {
+        sqlx::query_scalar::<_, NaiveDate>(
+            "SELECT COALESCE(stored.date, raw.date)
+             FROM daily_totals AS stored
+             FULL OUTER JOIN (
+                 SELECT date(created_at_local) AS date,
+                        SUM(amount) AS total_ml,
+                        COUNT(*) AS sip_count
+                 FROM sips
+                 GROUP BY date(created_at_local)
+             ) AS raw ON raw.date = stored.date
+             WHERE stored.total_ml IS NOT raw.total_ml
+                OR stored.sip_count IS NOT raw.sip_count
+             ORDER BY 1",
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+impl SessionRepository for SqliteRepository {
+    async fn insert_session(
+        &self,
+        session_uuid: &str,
+        session_start: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        // Starting with a heartbeat tells this session apart from those
+        // stored before heartbeats existed
+        let session_start = format_timestamp(session_start);
+
+        let result = sqlx::query!(
+            "INSERT INTO sessions (session_id, session_start, last_heartbeat) VALUES (?, ?, ?)",
+            session_uuid,
+            session_start,
+            session_start
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn session_heartbeat(&self, id: i64, at: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        let at = format_timestamp(at);
+
+        sqlx::query!(
+            "UPDATE sessions SET last_heartbeat = ? WHERE id = ? AND session_end IS NULL",
+            at,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn end_session(
+        &self,
+        id: i64,
+        session_end: DateTime<Utc>,
+        reason: SessionEndReason,
+    ) -> Result<(), sqlx::Error> {
+        let session_end = format_timestamp(session_end);
+        let reason = reason.as_str();
+
+        // A timer still running ends with the session
+        sqlx::query!(
+            "UPDATE timer_runs SET stopped_at = ? WHERE session_id = ? AND stopped_at IS NULL",
+            session_end,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE sessions SET session_end = ?, end_reason = ? WHERE id = ? AND session_end IS NULL",
+            session_end,
+            reason,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn recover_sessions(&self) -> Result<u64, sqlx::Error> {
+        let crashed = SessionEndReason::Crashed.as_str();
+        let unknown = SessionEndReason::Unknown.as_str();
+
+        // Timers left running stop at the latest of their start, the
+        // session's last heartbeat and their last sip
+        sqlx::query!(
+            "UPDATE timer_runs
+             SET stopped_at = MAX(
+                 started_at,
+                 COALESCE((SELECT last_heartbeat FROM sessions WHERE sessions.id = timer_runs.session_id), ''),
+                 COALESCE((SELECT MAX(created_at) FROM sips WHERE sips.run_id = timer_runs.id), '')
+             )
+             WHERE stopped_at IS NULL"
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // The latest of the start, the last heartbeat and the last sip.
+        // Sessions from before heartbeats were recorded may just as well have
+        // been quit, so their reason is unknown.
+        let result = sqlx::query!(
+            "UPDATE sessions
+             SET end_reason = CASE WHEN last_heartbeat IS NULL THEN ? ELSE ? END,
+                 session_end = MAX(
+                     session_start,
+                     COALESCE(last_heartbeat, ''),
+                     COALESCE((SELECT MAX(created_at) FROM sips WHERE sips.session_id = sessions.id), '')
+                 )
+             WHERE session_end IS NULL",
+            unknown,
+            crashed
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn session_summaries(&self) -> Result<Vec<SessionSummary>, sqlx::Error> {
+        sqlx::query_as::<_, SessionSummary>(
+            "SELECT sessions.id, sessions.session_id, session_start, session_end, end_reason,
+                    last_heartbeat,
+                    COUNT(sips.id) AS sip_count,
+                    COALESCE(SUM(sips.amount), 0) AS total_ml
+             FROM sessions
+             LEFT JOIN sips ON sips.session_id = sessions.id
+             GROUP BY sessions.id
+             ORDER BY sessions.id DESC",
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn start_timer_run(
+        &self,
+        session_id: i64,
+        started_at: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        let started_at = format_timestamp(started_at);
+
+        let result = sqlx::query!(
+            "INSERT INTO timer_runs (session_id, started_at) VALUES (?, ?)",
+            session_id,
+            started_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn stop_timer_run(
+        &self,
+        run_id: i64,
+        stopped_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let stopped_at = format_timestamp(stopped_at);
+
+        sqlx::query!(
+            "UPDATE timer_runs SET stopped_at = ? WHERE id = ? AND stopped_at IS NULL",
+            stopped_at,
+            run_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn saved_timer_started(&self) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>("SELECT timer_started FROM timer_state WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await
+            .map(|timer_started| timer_started.unwrap_or(false))
+    }
+
+    async fn save_timer_started(&self, timer_started: bool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE timer_state SET timer_started = ? WHERE id = 1",
+            timer_started
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn timer_run_summaries(
+        &self,
+        session_id: Option<i64>,
+    ) -> Result<Vec<TimerRunSummary>, sqlx::Error> {
+        sqlx::query_as::<_, TimerRunSummary>(
+            "SELECT timer_runs.id, timer_runs.session_id, started_at, stopped_at,
+                    COUNT(sips.id) AS sip_count,
+                    COALESCE(SUM(sips.amount), 0) AS total_ml
+             FROM timer_runs
+             LEFT JOIN sips ON sips.run_id = timer_runs.id
+             WHERE ?1 IS NULL OR timer_runs.session_id = ?1
+             GROUP BY timer_runs.id
+             ORDER BY timer_runs.id DESC",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+    use chrono_tz::Pacific::Kiritimati;
+
+    use super::*;
+    use crate::{clock::at_local, db::Database};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    /// A wall clock time fourteen hours ahead of UTC, so local days can't
+    /// line up with UTC's or, most likely, with the process's
+    fn local(date: NaiveDate, hour: u32, minute: u32) -> DateTime<LocalZone> {
+        let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+        at_local(&LocalZone::from(Kiritimati), date, time).unwrap()
+    }
+
+    async fn setup() -> (SqliteRepository, i64) {
+        let db = Database::in_memory().await.unwrap();
+        let repo = SqliteRepository::new(db.pool);
+        let session_id = repo
+            .insert_session("test", local(day(1), 8, 0).to_utc())
+            .await
+            .unwrap();
+        (repo, session_id)
+    }
+
+    async fn insert(
+        repo: &SqliteRepository,
+        session_id: i64,
+        amount: i64,
+        at: DateTime<LocalZone>,
+    ) -> i64 {
+        repo.insert_sip(amount, session_id, None, at, SipSource::Tray, None)
+            .await
+            .unwrap()
+    }
+
+    async fn totals_of(repo: &SqliteRepository, date: NaiveDate) -> Option<(i64, i64)> {
+        repo.daily_total(date)
+            .await
+            .unwrap()
+            .map(|total| (total.total_ml, total.sip_count))
+    }
+
+    #[tokio::test]
+    async fn inserts_add_up_per_local_day() {
+        let (repo, session_id) = setup().await;
+        insert(&repo, session_id, 100, local(day(10), 9, 0)).await;
+        insert(&repo, session_id, 250, local(day(10), 23, 59)).await;
+        insert(&repo, session_id, 50, local(day(11), 0, 0)).await;
+
+        let totals = repo.daily_totals(day(9), day(12)).await.unwrap();
+        assert_eq!(
+            totals
+                .iter()
+                .map(|total| (total.date, total.total_ml, total.sip_count))
+                .collect::<Vec<_>>(),
+            vec![(day(10), 350, 2), (day(11), 50, 1)]
+        );
+        assert_eq!(totals[0].goal_ml, 2000);
+        assert!(!totals[0].goal_met);
+        assert!(repo.daily_totals_mismatches().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn edits_and_deletes_update_the_day() {
+        let (repo, session_id) = setup().await;
+        let first = insert(&repo, session_id, 100, local(day(10), 9, 0)).await;
+        let second = insert(&repo, session_id, 200, local(day(10), 10, 0)).await;
+
+        repo.merge_into_sip(first, 150, None).await.unwrap();
+        assert_eq!(totals_of(&repo, day(10)).await, Some((450, 2)));
+
+        sqlx::query("UPDATE sips SET amount = 20 WHERE id = ?")
+            .bind(second)
+            .execute(repo.pool())
+            .await
+            .unwrap();
+        assert_eq!(totals_of(&repo, day(10)).await, Some((270, 2)));
+
+        sqlx::query("DELETE FROM sips WHERE id = ?")
+            .bind(first)
+            .execute(repo.pool())
+            .await
+            .unwrap();
+        assert_eq!(totals_of(&repo, day(10)).await, Some((20, 1)));
+        assert!(repo.daily_totals_mismatches().await.unwrap().is_empty());
+
+        // The last sip of a day takes its row with it
+        sqlx::query("DELETE FROM sips WHERE id = ?")
+            .bind(second)
+            .execute(repo.pool())
+            .await
+            .unwrap();
+        assert_eq!(totals_of(&repo, day(10)).await, None);
+        assert!(repo.daily_totals_mismatches().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn moving_a_sip_across_local_midnight_moves_its_total() {
+        let (repo, session_id) = setup().await;
+        let late = insert(&repo, session_id, 300, local(day(10), 23, 50)).await;
+        insert(&repo, session_id, 100, local(day(10), 12, 0)).await;
+        insert(&repo, session_id, 40, local(day(11), 8, 0)).await;
+
+        let move_to = |at: DateTime<LocalZone>| {
+            sqlx::query("UPDATE sips SET created_at = ?, created_at_local = ? WHERE id = ?")
+                .bind(format_timestamp(at.to_utc()))
+                .bind(format_local_timestamp(&at))
+                .bind(late)
+                .execute(repo.pool())
+        };
+
+        move_to(local(day(11), 0, 10)).await.unwrap();
+        assert_eq!(totals_of(&repo, day(10)).await, Some((100, 1)));
+        assert_eq!(totals_of(&repo, day(11)).await, Some((340, 2)));
+
+        // Onto a day without sips of its own, and back
+        move_to(local(day(9), 23, 59)).await.unwrap();
+        assert_eq!(totals_of(&repo, day(9)).await, Some((300, 1)));
+        assert_eq!(totals_of(&repo, day(11)).await, Some((40, 1)));
+
+        move_to(local(day(10), 0, 0)).await.unwrap();
+        assert_eq!(totals_of(&repo, day(9)).await, None);
+        assert_eq!(totals_of(&repo, day(10)).await, Some((400, 2)));
+        assert!(repo.daily_totals_mismatches().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mismatches_report_days_out_of_step() {
+        let (repo, session_id) = setup().await;
+        assert!(repo.daily_totals_mismatches().await.unwrap().is_empty());
+
+        insert(&repo, session_id, 100, local(day(10), 9, 0)).await;
+        insert(&repo, session_id, 100, local(day(11), 9, 0)).await;
+        assert!(repo.daily_totals_mismatches().await.unwrap().is_empty());
+
+        sqlx::query("UPDATE daily_totals SET total_ml = 1 WHERE date = ?")
+            .bind(day(11))
+            .execute(repo.pool())
+            .await
+            .unwrap();
+        assert_eq!(repo.daily_totals_mismatches().await.unwrap(), vec![day(11)]);
+    }
+
+    #[tokio::test]
+    async fn recovery_tells_crashes_from_sessions_without_heartbeats() {
+        // The session from setup is still open, as after a crash
+        let (repo, _) = setup().await;
+        let old_start = local(day(1), 7, 0).to_utc();
+        sqlx::query("INSERT INTO sessions (session_id, session_start) VALUES ('old', ?)")
+            .bind(format_timestamp(old_start))
+            .execute(repo.pool())
+            .await
+            .unwrap();
+        let quit = repo
+            .insert_session("quit", local(day(2), 8, 0).to_utc())
+            .await
+            .unwrap();
+        repo.end_session(quit, local(day(2), 9, 0).to_utc(), SessionEndReason::Update)
+            .await
+            .unwrap();
+
+        assert_eq!(repo.recover_sessions().await.unwrap(), 2);
+
+        let summaries = repo.session_summaries().await.unwrap();
+        let old = summaries.iter().find(|s| s.session_id == "old").unwrap();
+        assert_eq!(old.session_end, Some(old_start));
+
+        let reasons: Vec<_> = summaries
+            .into_iter()
+            .map(|session| (session.session_id, session.end_reason))
+            .collect();
+        assert!(reasons.contains(&("old".to_string(), Some(SessionEndReason::Unknown))));
+        assert!(reasons.contains(&("test".to_string(), Some(SessionEndReason::Crashed))));
+        assert!(reasons.contains(&("quit".to_string(), Some(SessionEndReason::Update))));
+    }
+}
@@ -0,0 +1,294 @@
+use chrono::{DateTime, Days, NaiveTime, TimeDelta};
+use serde::Serialize;
+
+use crate::{
+    clock::{at_local, start_of_day, LocalZone},
+    settings::AppSettings,
+};
+
+/// Paced reminders never come closer together than this
+const MIN_REMINDER_INTERVAL_MINUTES: i64 = 1;
+/// Upper bound on simulated reminders so a bad policy can't spin forever
+const MAX_PREVIEW_REMINDERS: usize = 24 * 60;
+
+/// The rules deciding when the user gets reminded to take a sip.
+///
+/// All methods take the current time as an argument instead of reading the
+/// system clock, so the same policy drives both the live reminder loop and
+/// the schedule preview.
+#[derive(Debug, Clone)]
+pub struct ReminderPolicy {
+    interval: TimeDelta,
+    quiet_hours: Option<(NaiveTime, NaiveTime)>,
+    pacing_enabled: bool,
+    anchors: Vec<NaiveTime>,
+    daily_goal_ml: i64,
+    sip_amount_ml: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulePreview {
+    pub reminders: Vec<DateTime<LocalZone>>,
+    pub projected_total_ml: i64,
+    pub daily_goal_ml: i64,
+    pub projected_goal_met: bool,
+}
+
+impl ReminderPolicy {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        let quiet_hours = match (settings.quiet_hours_start, settings.quiet_hours_end) {
+            (Some(start), Some(end)) if start != end => Some((start, end)),
+            _ => None,
+        };
+
+        let mut anchors = settings.anchor_times.clone();
+        anchors.sort();
+        anchors.dedup();
+
+        Self {
+            interval: TimeDelta::minutes(settings.notification_interval_minutes as i64),
+            quiet_hours,
+            pacing_enabled: settings.pacing_enabled,
+            anchors,
+            daily_goal_ml: settings.daily_goal_ml,
+            sip_amount_ml: settings.sip_amount_ml,
+        }
+    }
+
+    pub fn is_quiet(&self, time: NaiveTime) -> bool {
+        match self.quiet_hours {
+            Some((start, end)) if start < end => time >= start && time < end,
+            // Quiet hours wrap past midnight, e.g. 22:00 - 07:00
+            Some((start, end)) => time >= start || time < end,
+            None => false,
+        }
+    }
+
+    pub fn is_due(
+        &self,
+        last_sip: Option<DateTime<LocalZone>>,
+        total_today_ml: i64,
+        now: DateTime<LocalZone>,
+    ) -> bool {
+        !self.is_quiet(now.time()) && now >= self.next_reminder(last_sip, total_today_ml, now)
+    }
+
+    /// When the next reminder should fire, given the last sip and today's total.
+    /// The result may lie in the past if a reminder is already overdue.
+    pub fn next_reminder(
+        &self,
+        last_sip: Option<DateTime<LocalZone>>,
+        total_today_ml: i64,
+        now: DateTime<LocalZone>,
+    ) -> DateTime<LocalZone> {
+        let Some(last_sip) = last_sip else {
+            // Nothing logged yet, remind as soon as we're allowed to
+            return self.defer_past_quiet_hours(now);
+        };
+
+        let mut next = last_sip + self.effective_interval(last_sip, total_today_ml);
+        if let Some(anchor) = self.next_anchor_after(last_sip) {
+            next = next.min(anchor);
+        }
+
+        self.defer_past_quiet_hours(next)
+    }
+
+    /// Runs the policy forward from `now` until the end of the day, assuming
+    /// the user takes a sip every time they get reminded.
+    pub fn preview(
+        &self,
+        last_sip: Option<DateTime<LocalZone>>,
+        total_today_ml: i64,
+        now: DateTime<LocalZone>,
+    ) -> SchedulePreview {
+        let end_of_day = start_of_day(&now.timezone(), now.date_naive() + Days::new(1));
+
+        let mut reminders = Vec::new();
+        let mut last_sip = last_sip;
+        let mut total_ml = total_today_ml;
+        let mut cursor = now;
+
+        while reminders.len() < MAX_PREVIEW_REMINDERS {
+            let next = self.next_reminder(last_sip, total_ml, cursor).max(cursor);
+            if next >= end_of_day {
+                break;
+            }
+
+            reminders.push(next);
+            last_sip = Some(next);
+            total_ml += self.sip_amount_ml;
+            cursor = next;
+        }
+
+        SchedulePreview {
+            reminders,
+            projected_total_ml: total_ml,
+            daily_goal_ml: self.daily_goal_ml,
+            projected_goal_met: total_ml >= self.daily_goal_ml,
+        }
+    }
+
+    /// With pacing enabled the interval shrinks so the remaining goal can still
+    /// be reached before quiet hours (or midnight) begin.
+    fn effective_interval(&self, from: DateTime<LocalZone>, total_today_ml: i64) -> TimeDelta {
+        let remaining_ml = self.daily_goal_ml - total_today_ml;
+        if !self.pacing_enabled || remaining_ml <= 0 || self.sip_amount_ml <= 0 {
+            return self.interval;
+        }
+
+        let sips_needed = (remaining_ml + self.sip_amount_ml - 1) / self.sip_amount_ml;
+        let window = self.active_window_end(from) - from;
+        if window <= TimeDelta::zero() {
+            return self.interval;
+        }
+
+        let paced = window / sips_needed.min(i32::MAX as i64) as i32;
+        paced.clamp(
+            TimeDelta::minutes(MIN_REMINDER_INTERVAL_MINUTES),
+            self.interval
+                .max(TimeDelta::minutes(MIN_REMINDER_INTERVAL_MINUTES)),
+        )
+    }
+
+    /// The end of the stretch of the day in which reminders may fire.
+    fn active_window_end(&self, from: DateTime<LocalZone>) -> DateTime<LocalZone> {
+        let end_of_day = start_of_day(&from.timezone(), from.date_naive() + Days::new(1));
+
+        match self.quiet_hours {
+            Some((start, _)) => match at_local(&from.timezone(), from.date_naive(), start) {
+                Some(quiet_start) if quiet_start > from => quiet_start,
+                _ => end_of_day,
+            },
+            None => end_of_day,
+        }
+    }
+
+    fn next_anchor_after(&self, after: DateTime<LocalZone>) -> Option<DateTime<LocalZone>> {
+        let today = after.date_naive();
+        let tomorrow = today + Days::new(1);
+
+        [today, tomorrow].into_iter().find_map(|date| {
+            self.anchors
+                .iter()
+                .filter_map(|anchor| at_local(&after.timezone(), date, *anchor))
+                .find(|anchor| *anchor > after)
+        })
+    }
+
+    fn defer_past_quiet_hours(&self, time: DateTime<LocalZone>) -> DateTime<LocalZone> {
+        let Some((_, end)) = self.quiet_hours else {
+            return time;
+        };
+        if !self.is_quiet(time.time()) {
+            return time;
+        }
+
+        let date = if end > time.time() {
+            time.date_naive()
+        } else {
+            time.date_naive() + Days::new(1)
+        };
+
+        at_local(&time.timezone(), date, end).unwrap_or(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+    use chrono_tz::Europe::Berlin;
+
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn policy() -> ReminderPolicy {
+        ReminderPolicy::from_settings(&AppSettings {
+            notification_interval_minutes: 60,
+            quiet_hours_start: Some(time(22, 0)),
+            quiet_hours_end: Some(time(7, 0)),
+            daily_goal_ml: 2000,
+            sip_amount_ml: 100,
+            ..AppSettings::default()
+        })
+    }
+
+    /// Ticks the clock minute by minute, drinking whenever a reminder is due
+    fn run(
+        policy: &ReminderPolicy,
+        clock: &ManualClock,
+        until: DateTime<Utc>,
+    ) -> Vec<DateTime<LocalZone>> {
+        let mut reminders = Vec::new();
+        let mut last_sip = None;
+        let mut today = clock.local_now().date_naive();
+        let mut total_ml = 0;
+
+        while clock.now() < until {
+            let now = clock.local_now();
+            if now.date_naive() != today {
+                today = now.date_naive();
+                total_ml = 0;
+            }
+
+            if policy.is_due(last_sip, total_ml, now) {
+                reminders.push(now);
+                last_sip = Some(now);
+                total_ml += policy.sip_amount_ml;
+            }
+            clock.advance(TimeDelta::minutes(1));
+        }
+
+        reminders
+    }
+
+    #[test]
+    fn reminders_over_several_days_respect_quiet_hours_across_dst() {
+        let policy = policy();
+        // Berlin switches to summer time on the night of 2025-03-30
+        let clock = ManualClock::new("2025-03-28T11:00:00Z".parse().unwrap()).in_zone(Berlin);
+        let reminders = run(&policy, &clock, "2025-03-31T22:00:00Z".parse().unwrap());
+
+        assert!(reminders.iter().all(|at| !policy.is_quiet(at.time())));
+
+        let days: Vec<NaiveDate> = (28..=31)
+            .map(|day| NaiveDate::from_ymd_opt(2025, 3, day).unwrap())
+            .collect();
+        for day in &days {
+            let of_day: Vec<_> = reminders
+                .iter()
+                .filter(|at| at.date_naive() == *day)
+                .collect();
+            assert!(!of_day.is_empty(), "no reminders on {}", day);
+            assert_eq!(of_day.last().unwrap().time(), time(21, 0));
+
+            for pair in of_day.windows(2) {
+                assert_eq!(*pair[1] - *pair[0], TimeDelta::hours(1));
+            }
+        }
+
+        // Every day after the first starts when quiet hours end, DST or not
+        for day in &days[1..] {
+            let first = reminders.iter().find(|at| at.date_naive() == *day).unwrap();
+            assert_eq!(first.time(), time(7, 0));
+        }
+        assert_eq!(reminders.len(), 10 + 3 * 15);
+    }
+
+    #[test]
+    fn preview_ends_at_the_local_end_of_day() {
+        let policy = policy();
+        let clock = ManualClock::new("2025-03-30T05:00:00Z".parse().unwrap()).in_zone(Berlin);
+        let preview = policy.preview(None, 0, clock.local_now());
+
+        assert_eq!(preview.reminders.first().unwrap().time(), time(7, 0));
+        assert_eq!(preview.reminders.last().unwrap().time(), time(21, 0));
+        assert_eq!(preview.reminders.len(), 15);
+        assert_eq!(preview.projected_total_ml, 1500);
+        assert!(!preview.projected_goal_met);
+    }
+}
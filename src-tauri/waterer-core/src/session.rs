@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+/// How often a running session records that it is still alive
+pub const HEARTBEAT_INTERVAL_SECONDS: u64 = 60;
+
+/// Why a session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum SessionEndReason {
+    /// The app was quit normally
+    Quit,
+    /// The session was still open when the app started again, so the app
+    /// crashed or was killed
+    Crashed,
+    /// The app restarted to install an update
+    Update,
+    /// The session is from before the app recorded how sessions end
+    Unknown,
+}
+
+impl SessionEndReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionEndReason::Quit => "quit",
+            SessionEndReason::Crashed => "crashed",
+            SessionEndReason::Update => "update",
+            SessionEndReason::Unknown => "unknown",
+        }
+    }
+}
+
+/// A session with what was drunk during it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromRow)]
+pub struct SessionSummary {
+    pub id: i64,
+    pub session_id: String,
+    pub session_start: DateTime<Utc>,
+    /// None while the session is running
+    pub session_end: Option<DateTime<Utc>>,
+    pub end_reason: Option<SessionEndReason>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    /// Up to the end, or up to now for the running session
+    #[sqlx(skip)]
+    pub duration_seconds: i64,
+    pub sip_count: i64,
+    pub total_ml: i64,
+}
+
+impl SessionSummary {
+    /// Fills in the duration, measuring a running session up to `now`
+    pub fn with_duration(mut self, now: DateTime<Utc>) -> Self {
+        let end = self.session_end.unwrap_or(now);
+        self.duration_seconds = (end - self.session_start).num_seconds().max(0);
+        self
+    }
+}
+
+/// One "timer started, timer stopped" interval of a session with what was
+/// drunk during it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromRow)]
+pub struct TimerRunSummary {
+    pub id: i64,
+    pub session_id: i64,
+    pub started_at: DateTime<Utc>,
+    /// None while the timer is running
+    pub stopped_at: Option<DateTime<Utc>>,
+    /// Up to the stop, or up to now for the running timer
+    #[sqlx(skip)]
+    pub duration_seconds: i64,
+    pub sip_count: i64,
+    pub total_ml: i64,
+}
+
+impl TimerRunSummary {
+    /// Fills in the duration, measuring a running timer up to `now`
+    pub fn with_duration(mut self, now: DateTime<Utc>) -> Self {
+        let end = self.stopped_at.unwrap_or(now);
+        self.duration_seconds = (end - self.started_at).num_seconds().max(0);
+        self
+    }
+}
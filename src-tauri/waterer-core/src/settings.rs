@@ -0,0 +1,383 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    audio::ReminderSounds,
+    message::{Catalog, MessageTemplates},
+    notifier::{NotificationChannel, NotificationRoutes},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    pub timer_interval_ms: u64,
+    pub sip_amount_ml: i64,
+    pub notifications_enabled: bool,
+    pub start_minimized: bool,
+    pub daily_goal_ml: i64,
+    pub notification_interval_minutes: u64,
+    pub quiet_hours_start: Option<NaiveTime>,
+    pub quiet_hours_end: Option<NaiveTime>,
+    /// Shorten the reminder interval when falling behind the daily goal
+    pub pacing_enabled: bool,
+    /// Fixed times of day that always trigger a reminder
+    pub anchor_times: Vec<NaiveTime>,
+    /// How far below the daily goal a day may fall and still count towards a streak
+    pub streak_tolerance_percent: u8,
+    /// Also notify at 25, 50 and 75 percent of the daily goal
+    pub goal_milestone_notifications: bool,
+    /// Most that may be drunk within any rolling hour before sips need confirmation
+    pub max_hourly_intake_ml: i64,
+    /// Most that may be drunk in a day before sips need confirmation
+    pub max_daily_intake_ml: i64,
+    /// Sips closer together than this are treated as accidental duplicates, 0 turns the check off
+    pub min_sip_gap_seconds: i64,
+    /// Add duplicate sips to the previous one instead of asking for confirmation
+    pub merge_duplicate_sips: bool,
+    /// Whether the timer starts by itself at launch or when working hours begin
+    pub timer_auto_start: TimerAutoStart,
+    /// Local times the working day starts and ends, used by [`TimerAutoStart::WorkingHours`]
+    pub working_hours_start: Option<NaiveTime>,
+    pub working_hours_end: Option<NaiveTime>,
+    /// Hold reminders while the screen is locked or the session is idle, then send one on return
+    pub pause_when_away: bool,
+    /// Minutes without input after which the session counts as idle
+    pub away_idle_minutes: i64,
+    /// Amount logged by the "Log glass" reminder action
+    pub glass_amount_ml: i64,
+    /// Show "Log sip", "Log glass" and "Snooze" buttons on reminders where the platform supports them
+    pub actionable_notifications: bool,
+    /// Channels tried in order for each kind of notification
+    pub notification_routes: NotificationRoutes,
+    /// Program and arguments run by the command channel, `{title}`, `{body}` and `{kind}` are filled in
+    pub notification_command: Vec<String>,
+    /// Language of the notifications, the system's when not set
+    pub locale: Option<String>,
+    /// Notification texts replacing the catalog's
+    pub message_templates: MessageTemplates,
+    /// Volume of notification sounds
+    pub sound_volume_percent: u8,
+    /// Sounds played with each kind of notification, by escalation step
+    pub reminder_sounds: ReminderSounds,
+}
+
+/// When the timer starts without the user starting it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerAutoStart {
+    /// Only restore the state the timer was in when the app last ran
+    #[default]
+    Restore,
+    /// Always start the timer at launch
+    Launch,
+    /// Start the timer at launch within working hours and whenever they begin
+    WorkingHours,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            timer_interval_ms: 1000,
+            sip_amount_ml: 35,
+            notifications_enabled: true,
+            start_minimized: true,
+            daily_goal_ml: 2000,
+            notification_interval_minutes: 10,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            pacing_enabled: false,
+            anchor_times: Vec::new(),
+            streak_tolerance_percent: 0,
+            goal_milestone_notifications: false,
+            max_hourly_intake_ml: 1000,
+            max_daily_intake_ml: 10000,
+            min_sip_gap_seconds: 5,
+            merge_duplicate_sips: false,
+            timer_auto_start: TimerAutoStart::Restore,
+            working_hours_start: None,
+            working_hours_end: None,
+            pause_when_away: true,
+            away_idle_minutes: 5,
+            glass_amount_ml: 250,
+            actionable_notifications: true,
+            notification_routes: NotificationRoutes::default(),
+            notification_command: Vec::new(),
+            locale: None,
+            message_templates: MessageTemplates::default(),
+            sound_volume_percent: 80,
+            reminder_sounds: ReminderSounds::default(),
+        }
+    }
+}
+
+impl AppSettings {
+    pub fn load() -> Self {
+        let config_path = Self::config_path();
+
+        if config_path.exists() {
+            match fs::read_to_string(&config_path) {
+                Ok(content) => {
+                    match toml::from_str::<AppSettings>(&content) {
+                        Ok(settings) => {
+                            // Validate settings before returning
+                            if settings.is_valid() {
+                                settings
+                            } else {
+                                eprintln!("Invalid settings found, using defaults");
+                                let defaults = Self::default();
+                                if let Err(e) = defaults.save() {
+                                    eprintln!("Failed to save default settings: {}", e);
+                                }
+                                defaults
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to parse settings file: {}. Using defaults.", e);
+                            let defaults = Self::default();
+                            if let Err(e) = defaults.save() {
+                                eprintln!("Failed to save default settings: {}", e);
+                            }
+                            defaults
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to read settings file: {}. Using defaults.", e);
+                    Self::default()
+                }
+            }
+        } else {
+            let defaults = Self::default();
+            if let Err(e) = defaults.save() {
+                eprintln!("Failed to save default settings: {}", e);
+            }
+            defaults
+        }
+    }
+
+    fn config_path() -> PathBuf {
+        config_dir().join("settings.toml")
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let config_path = Self::config_path();
+
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = toml::to_string_pretty(self)?;
+        fs::write(&config_path, content)?;
+
+        Ok(())
+    }
+
+    fn is_valid(&self) -> bool {
+        self.timer_interval_ms > 0
+            && self.sip_amount_ml > 0
+            && self.daily_goal_ml > 0
+            && self.timer_interval_ms <= 86400000 // Max 24 hours
+            && self.sip_amount_ml <= 1000 // Max 1L per sip
+            && self.daily_goal_ml <= 10000 // Max 10L per day
+            && self.notification_interval_minutes > 0
+            && self.notification_interval_minutes <= 180 // Max 3 hours
+            && self.quiet_hours_start.is_some() == self.quiet_hours_end.is_some()
+            && self.streak_tolerance_percent <= 50 // Max half the goal
+            && self.max_hourly_intake_ml > 0
+            && self.max_hourly_intake_ml <= 5000 // Max 5L per hour
+            && self.max_daily_intake_ml >= self.daily_goal_ml
+            && self.max_daily_intake_ml >= self.max_hourly_intake_ml
+            && self.max_daily_intake_ml <= 20000 // Max 20L per day
+            && self.min_sip_gap_seconds >= 0
+            && self.min_sip_gap_seconds <= 300 // Max 5 minutes
+            && (self.timer_auto_start != TimerAutoStart::WorkingHours
+                || self.working_hours_start.is_some())
+            && self.working_hours_start.is_some() == self.working_hours_end.is_some()
+            && self.away_idle_minutes > 0
+            && self.away_idle_minutes <= 120 // Max 2 hours
+            && self.glass_amount_ml > 0
+            && self.glass_amount_ml <= 1000 // Max 1L per glass
+            && self.notification_routes.is_valid()
+            && (!self.notification_routes.uses(NotificationChannel::Command)
+                || !self.notification_command.is_empty())
+            && self.locale.as_deref().is_none_or(Catalog::is_supported)
+            && self.message_templates.validate().is_ok()
+            && self.sound_volume_percent <= 100
+    }
+
+    /// Whether `time` falls within the working hours, false if none are set
+    pub fn is_working_hours(&self, time: NaiveTime) -> bool {
+        match (self.working_hours_start, self.working_hours_end) {
+            (Some(start), Some(end)) if start < end => time >= start && time < end,
+            // Working hours wrap past midnight, e.g. a night shift from 22:00 - 06:00
+            (Some(start), Some(end)) if start > end => time >= start || time < end,
+            _ => false,
+        }
+    }
+
+    pub fn update_with_partial(&mut self, partial: PartialAppSettings) -> anyhow::Result<()> {
+        if let Some(timer_interval_ms) = partial.timer_interval_ms {
+            self.timer_interval_ms = timer_interval_ms;
+        }
+        if let Some(sip_amount_ml) = partial.sip_amount_ml {
+            self.sip_amount_ml = sip_amount_ml;
+        }
+        if let Some(notifications_enabled) = partial.notifications_enabled {
+            self.notifications_enabled = notifications_enabled;
+        }
+        if let Some(start_minimized) = partial.start_minimized {
+            self.start_minimized = start_minimized;
+        }
+        if let Some(daily_goal_ml) = partial.daily_goal_ml {
+            self.daily_goal_ml = daily_goal_ml;
+        }
+        if let Some(notification_interval_minutes) = partial.notification_interval_minutes {
+            self.notification_interval_minutes = notification_interval_minutes;
+        }
+        if let Some(quiet_hours_start) = partial.quiet_hours_start {
+            self.quiet_hours_start = quiet_hours_start;
+        }
+        if let Some(quiet_hours_end) = partial.quiet_hours_end {
+            self.quiet_hours_end = quiet_hours_end;
+        }
+        if let Some(pacing_enabled) = partial.pacing_enabled {
+            self.pacing_enabled = pacing_enabled;
+        }
+        if let Some(anchor_times) = partial.anchor_times {
+            self.anchor_times = anchor_times;
+        }
+        if let Some(streak_tolerance_percent) = partial.streak_tolerance_percent {
+            self.streak_tolerance_percent = streak_tolerance_percent;
+        }
+        if let Some(goal_milestone_notifications) = partial.goal_milestone_notifications {
+            self.goal_milestone_notifications = goal_milestone_notifications;
+        }
+        if let Some(max_hourly_intake_ml) = partial.max_hourly_intake_ml {
+            self.max_hourly_intake_ml = max_hourly_intake_ml;
+        }
+        if let Some(max_daily_intake_ml) = partial.max_daily_intake_ml {
+            self.max_daily_intake_ml = max_daily_intake_ml;
+        }
+        if let Some(min_sip_gap_seconds) = partial.min_sip_gap_seconds {
+            self.min_sip_gap_seconds = min_sip_gap_seconds;
+        }
+        if let Some(merge_duplicate_sips) = partial.merge_duplicate_sips {
+            self.merge_duplicate_sips = merge_duplicate_sips;
+        }
+        if let Some(timer_auto_start) = partial.timer_auto_start {
+            self.timer_auto_start = timer_auto_start;
+        }
+        if let Some(working_hours_start) = partial.working_hours_start {
+            self.working_hours_start = working_hours_start;
+        }
+        if let Some(working_hours_end) = partial.working_hours_end {
+            self.working_hours_end = working_hours_end;
+        }
+        if let Some(pause_when_away) = partial.pause_when_away {
+            self.pause_when_away = pause_when_away;
+        }
+        if let Some(away_idle_minutes) = partial.away_idle_minutes {
+            self.away_idle_minutes = away_idle_minutes;
+        }
+        if let Some(glass_amount_ml) = partial.glass_amount_ml {
+            self.glass_amount_ml = glass_amount_ml;
+        }
+        if let Some(actionable_notifications) = partial.actionable_notifications {
+            self.actionable_notifications = actionable_notifications;
+        }
+        if let Some(notification_routes) = partial.notification_routes {
+            self.notification_routes = notification_routes;
+        }
+        if let Some(notification_command) = partial.notification_command {
+            self.notification_command = notification_command;
+        }
+        if let Some(locale) = partial.locale {
+            self.locale = locale;
+        }
+        if let Some(message_templates) = partial.message_templates {
+            // Say which template is wrong rather than just rejecting the settings
+            message_templates.validate()?;
+            self.message_templates = message_templates;
+        }
+        if let Some(sound_volume_percent) = partial.sound_volume_percent {
+            self.sound_volume_percent = sound_volume_percent;
+        }
+        if let Some(reminder_sounds) = partial.reminder_sounds {
+            // Checked here only, a sound file going missing later shouldn't
+            // reset every setting on the next launch
+            reminder_sounds.validate()?;
+            self.reminder_sounds = reminder_sounds;
+        }
+
+        // Validate the updated settings
+        if !self.is_valid() {
+            return Err(anyhow::anyhow!("Invalid settings after update"));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialAppSettings {
+    pub timer_interval_ms: Option<u64>,
+    pub sip_amount_ml: Option<i64>,
+    pub notifications_enabled: Option<bool>,
+    pub start_minimized: Option<bool>,
+    pub daily_goal_ml: Option<i64>,
+    pub notification_interval_minutes: Option<u64>,
+    /// An explicit `null` clears the quiet hours
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub quiet_hours_start: Option<Option<NaiveTime>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub quiet_hours_end: Option<Option<NaiveTime>>,
+    pub pacing_enabled: Option<bool>,
+    pub anchor_times: Option<Vec<NaiveTime>>,
+    pub streak_tolerance_percent: Option<u8>,
+    pub goal_milestone_notifications: Option<bool>,
+    pub max_hourly_intake_ml: Option<i64>,
+    pub max_daily_intake_ml: Option<i64>,
+    pub min_sip_gap_seconds: Option<i64>,
+    pub merge_duplicate_sips: Option<bool>,
+    pub timer_auto_start: Option<TimerAutoStart>,
+    /// An explicit `null` clears the working hours
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub working_hours_start: Option<Option<NaiveTime>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub working_hours_end: Option<Option<NaiveTime>>,
+    pub pause_when_away: Option<bool>,
+    pub away_idle_minutes: Option<i64>,
+    pub glass_amount_ml: Option<i64>,
+    pub actionable_notifications: Option<bool>,
+    pub notification_routes: Option<NotificationRoutes>,
+    pub notification_command: Option<Vec<String>>,
+    /// An explicit `null` goes back to the system's language
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub locale: Option<Option<String>>,
+    pub message_templates: Option<MessageTemplates>,
+    pub sound_volume_percent: Option<u8>,
+    pub reminder_sounds: Option<ReminderSounds>,
+}
+
+/// Directory holding the settings file and other user configuration
+pub fn config_dir() -> PathBuf {
+    match dirs::config_local_dir() {
+        Some(dir) => dir.join("waterer"),
+        None => Path::new(".").join("waterer"),
+    }
+}
+
+/// Distinguishes a field set to `null` from a missing one
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,585 @@
+use crate::{
+    clock::{Clock, LocalZone},
+    repository::{DailyTotalsRepository, SipRepository},
+    schedule::ReminderPolicy,
+};
+use chrono::{self, DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+/// Where a sip was logged from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum SipSource {
+    Tray,
+    MainWindow,
+    Notification,
+    Automation,
+    /// Sips logged before the source was recorded
+    #[default]
+    Unknown,
+}
+
+impl SipSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SipSource::Tray => "tray",
+            SipSource::MainWindow => "main_window",
+            SipSource::Notification => "notification",
+            SipSource::Automation => "automation",
+            SipSource::Unknown => "unknown",
+        }
+    }
+}
+
+/// Where a new sip comes from and what it is attributed to
+#[derive(Debug, Clone, Copy)]
+pub struct SipOrigin<'a> {
+    pub session_id: i64,
+    /// The timer run in progress, if the timer is started
+    pub run_id: Option<i64>,
+    pub source: SipSource,
+    /// Id the client sent along with the sip, if any
+    pub request_id: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Sip {
+    pub id: i64,
+    pub amount: i64,
+    pub created_at: DateTime<Utc>,
+    pub notified_user: bool,
+    pub session_id: i64,
+    /// The timer run active when the sip was logged
+    pub run_id: Option<i64>,
+    /// Id the client sent along with the sip, if any
+    pub request_id: Option<String>,
+    pub source: SipSource,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SipState {
+    last_sip_timestamp: i64,
+    pub total_sips_today: i64,
+    pub total_amount_today: i64,
+    pub total_sips_all_time: i64,
+    pub total_amount_all_time: i64,
+    pub last_sip_id: Option<i64>,
+    pub notified_user: bool,
+    /// The local day the `*_today` totals belong to
+    today: Option<NaiveDate>,
+}
+
+impl SipState {
+    pub async fn read_from_db(
+        &self,
+        repo: &(impl SipRepository + DailyTotalsRepository),
+        clock: &dyn Clock,
+    ) -> Self {
+        match Self::load(repo, clock).await {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Failed to fetch sips from database: {}", e);
+                Self {
+                    today: Some(clock.local_now().date_naive()),
+                    ..Self::default()
+                }
+            }
+        }
+    }
+
+    /// Builds the state from the `daily_totals` table instead of folding every sip
+    async fn load(
+        repo: &(impl SipRepository + DailyTotalsRepository),
+        clock: &dyn Clock,
+    ) -> Result<Self, sqlx::Error> {
+        let today = clock.local_now().date_naive();
+
+        let last_sip = repo.latest_sip().await?;
+        let all_time = repo.all_time_totals().await?;
+        let today_totals = repo.daily_total(today).await?;
+
+        let Some(last_sip) = last_sip else {
+            return Ok(Self {
+                today: Some(today),
+                ..Self::default()
+            });
+        };
+
+        Ok(Self {
+            last_sip_timestamp: last_sip.created_at.timestamp(),
+            total_amount_all_time: all_time.amount,
+            total_amount_today: today_totals.as_ref().map_or(0, |day| day.total_ml),
+            total_sips_all_time: all_time.count,
+            total_sips_today: today_totals.as_ref().map_or(0, |day| day.sip_count),
+            notified_user: last_sip.notified_user,
+            last_sip_id: Some(last_sip.id),
+            today: Some(today),
+        })
+    }
+
+    pub async fn take_sip(
+        &self,
+        amount: i64,
+        origin: SipOrigin<'_>,
+        repo: &impl SipRepository,
+        clock: &dyn Clock,
+    ) -> anyhow::Result<Self> {
+        let created_at = clock.local_now();
+        let sip_id = repo
+            .insert_sip(
+                amount,
+                origin.session_id,
+                origin.run_id,
+                created_at,
+                origin.source,
+                origin.request_id,
+            )
+            .await?;
+
+        let mut state = self.clone();
+        state.apply_sip(sip_id, amount, created_at.with_timezone(&Utc), clock);
+        Ok(state)
+    }
+
+    /// Adds `amount` to the latest sip instead of logging a new one
+    pub async fn merge_sip(
+        &self,
+        amount: i64,
+        request_id: Option<&str>,
+        repo: &impl SipRepository,
+        clock: &dyn Clock,
+    ) -> anyhow::Result<Self> {
+        let (Some(sip_id), Some(created_at)) = (self.last_sip_id, self.last_sip_at()) else {
+            return Err(anyhow::anyhow!("There is no sip to merge into"));
+        };
+
+        repo.merge_into_sip(sip_id, amount, request_id).await?;
+
+        let mut state = self.clone();
+        state.roll_over(clock);
+        state.total_amount_all_time += amount;
+        if Some(created_at.with_timezone(&clock.zone()).date_naive()) == state.today {
+            state.total_amount_today += amount;
+        }
+        Ok(state)
+    }
+
+    /// Whether the latest sip was logged less than `min_gap_seconds` before
+    /// `now`, returning how long ago it was
+    pub fn recent_sip(&self, now: DateTime<Utc>, min_gap_seconds: i64) -> Option<i64> {
+        let seconds_since_last = (now - self.last_sip_at()?).num_seconds();
+
+        (0..min_gap_seconds)
+            .contains(&seconds_since_last)
+            .then_some(seconds_since_last)
+    }
+
+    /// Folds a freshly stored sip into the totals without going back to the database
+    fn apply_sip(
+        &mut self,
+        sip_id: i64,
+        amount: i64,
+        created_at: DateTime<Utc>,
+        clock: &dyn Clock,
+    ) {
+        self.roll_over(clock);
+
+        self.last_sip_id = Some(sip_id);
+        self.last_sip_timestamp = created_at.timestamp();
+        self.notified_user = false;
+
+        self.total_sips_all_time += 1;
+        self.total_amount_all_time += amount;
+
+        if Some(created_at.with_timezone(&clock.zone()).date_naive()) == self.today {
+            self.total_sips_today += 1;
+            self.total_amount_today += amount;
+        }
+    }
+
+    /// Compares the in-memory totals with the database and adopts the
+    /// database's view if they drifted apart. Returns whether they matched.
+    pub async fn reconcile(
+        &mut self,
+        repo: &(impl SipRepository + DailyTotalsRepository),
+        clock: &dyn Clock,
+    ) -> bool {
+        let stored = match Self::load(repo, clock).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                eprintln!("Failed to reconcile sip totals: {}", e);
+                return true;
+            }
+        };
+
+        let in_sync = self.totals() == stored.totals();
+        if !in_sync {
+            eprintln!(
+                "Sip totals drifted from the database: memory {:?}, database {:?}",
+                self.totals(),
+                stored.totals()
+            );
+        }
+
+        *self = stored;
+        in_sync
+    }
+
+    fn totals(&self) -> (Option<i64>, i64, i64, i64, i64) {
+        (
+            self.last_sip_id,
+            self.total_sips_today,
+            self.total_amount_today,
+            self.total_sips_all_time,
+            self.total_amount_all_time,
+        )
+    }
+
+    /// Resets today's totals once the local date has moved on.
+    /// Returns whether a new day started.
+    pub fn roll_over(&mut self, clock: &dyn Clock) -> bool {
+        let today = clock.local_now().date_naive();
+        if self.today == Some(today) {
+            return false;
+        }
+
+        self.today = Some(today);
+        self.total_sips_today = 0;
+        self.total_amount_today = 0;
+        true
+    }
+
+    pub fn last_sip_at(&self) -> Option<DateTime<Utc>> {
+        self.last_sip_id?;
+        DateTime::from_timestamp(self.last_sip_timestamp, 0)
+    }
+
+    pub fn check_if_sip_is_due(&self, policy: &ReminderPolicy, now: DateTime<LocalZone>) -> bool {
+        let last_sip_local = self
+            .last_sip_at()
+            .map(|at| at.with_timezone(&now.timezone()));
+        policy.is_due(last_sip_local, self.total_amount_today, now)
+    }
+
+    pub async fn set_notified_user(
+        &mut self,
+        notified_user: bool,
+        repo: &impl SipRepository,
+    ) -> anyhow::Result<()> {
+        // Before the first sip there's no row to store it on, so it's only
+        // remembered until the app restarts
+        if let Some(last_sip_id) = self.last_sip_id {
+            repo.set_notified_user(last_sip_id, notified_user).await?;
+        }
+
+        // Update the local state
+        self.notified_user = notified_user;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use chrono_tz::{Europe::Berlin, Pacific::Kiritimati};
+
+    use super::*;
+    use crate::{
+        clock::ManualClock,
+        db::Database,
+        repository::{SessionRepository, SqliteRepository},
+    };
+
+    async fn setup() -> (SqliteRepository, ManualClock, i64) {
+        let db = Database::in_memory().await.unwrap();
+        let repo = SqliteRepository::new(db.pool);
+        let clock = ManualClock::new("2025-06-10T12:00:00Z".parse().unwrap());
+        let session_id = repo.insert_session("test", clock.now()).await.unwrap();
+        (repo, clock, session_id)
+    }
+
+    fn origin(session_id: i64, request_id: Option<&str>) -> SipOrigin<'_> {
+        SipOrigin {
+            session_id,
+            run_id: None,
+            source: SipSource::MainWindow,
+            request_id,
+        }
+    }
+
+    #[test]
+    fn totals_reset_at_local_midnight() {
+        let clock = ManualClock::new("2025-03-29T22:30:00Z".parse().unwrap()).in_zone(Berlin);
+        let mut state = SipState::default();
+        assert!(state.roll_over(&clock));
+
+        state.apply_sip(1, 100, clock.now(), &clock);
+        assert_eq!(state.total_amount_today, 100);
+
+        // 23:59 local, still the same day
+        clock.advance(TimeDelta::minutes(29));
+        assert!(!state.roll_over(&clock));
+        assert_eq!(state.total_amount_today, 100);
+
+        clock.advance(TimeDelta::minutes(1));
+        assert!(state.roll_over(&clock));
+        assert_eq!(state.total_amount_today, 0);
+        assert_eq!(state.total_amount_all_time, 100);
+    }
+
+    #[test]
+    fn sips_count_towards_the_local_day_they_were_taken_on() {
+        let clock = ManualClock::new("2025-03-30T00:10:00Z".parse().unwrap()).in_zone(Berlin);
+        let mut state = SipState::default();
+
+        // 23:50 local the evening before
+        state.apply_sip(1, 100, "2025-03-29T22:50:00Z".parse().unwrap(), &clock);
+        assert_eq!(state.total_amount_today, 0);
+        assert_eq!(state.total_amount_all_time, 100);
+
+        state.apply_sip(2, 150, clock.now(), &clock);
+        assert_eq!(state.total_amount_today, 150);
+        assert_eq!(state.total_sips_today, 1);
+    }
+
+    #[tokio::test]
+    async fn take_sip_stores_the_sip_and_updates_totals() {
+        let (repo, clock, session_id) = setup().await;
+        let state = SipState::default()
+            .read_from_db(&repo, &clock)
+            .await
+            .take_sip(100, origin(session_id, Some("first")), &repo, &clock)
+            .await
+            .unwrap();
+
+        clock.advance(TimeDelta::minutes(5));
+        let state = state
+            .take_sip(150, origin(session_id, None), &repo, &clock)
+            .await
+            .unwrap();
+
+        assert_eq!(state.total_sips_today, 2);
+        assert_eq!(state.total_amount_today, 250);
+        assert_eq!(state.total_amount_all_time, 250);
+        assert_eq!(state.last_sip_at(), Some(clock.now()));
+        assert!(!state.notified_user);
+
+        let first = repo.sip_by_request_id("first").await.unwrap().unwrap();
+        assert_eq!(first.amount, 100);
+        assert_eq!(first.session_id, session_id);
+        assert_eq!(first.source, SipSource::MainWindow);
+
+        let last = repo.sip(state.last_sip_id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(last.amount, 150);
+        assert_eq!(last.request_id, None);
+    }
+
+    #[tokio::test]
+    async fn read_from_db_totals_match_the_sips_taken() {
+        let (repo, clock, session_id) = setup().await;
+        let today = clock.now();
+
+        clock.set(today - TimeDelta::days(1));
+        let mut taken = SipState::default();
+        for amount in [200, 300] {
+            taken = taken
+                .take_sip(amount, origin(session_id, None), &repo, &clock)
+                .await
+                .unwrap();
+        }
+
+        clock.set(today);
+        for amount in [100, 50, 25] {
+            taken = taken
+                .take_sip(amount, origin(session_id, None), &repo, &clock)
+                .await
+                .unwrap();
+            clock.advance(TimeDelta::minutes(10));
+        }
+
+        let loaded = SipState::default().read_from_db(&repo, &clock).await;
+        assert_eq!(loaded.totals(), taken.totals());
+        assert_eq!(loaded.totals(), (taken.last_sip_id, 3, 175, 5, 675));
+        assert_eq!(loaded.last_sip_at(), taken.last_sip_at());
+    }
+
+    #[tokio::test]
+    async fn stored_totals_use_the_clock_zone() {
+        let (repo, _, session_id) = setup().await;
+        // 23:30 and 01:00 the next day in Kiritimati, both June 10th in UTC
+        let clock = ManualClock::new("2025-06-10T09:30:00Z".parse().unwrap()).in_zone(Kiritimati);
+        let state = SipState::default()
+            .take_sip(100, origin(session_id, None), &repo, &clock)
+            .await
+            .unwrap();
+        clock.advance(TimeDelta::minutes(90));
+        state
+            .take_sip(150, origin(session_id, None), &repo, &clock)
+            .await
+            .unwrap();
+
+        let loaded = SipState::default().read_from_db(&repo, &clock).await;
+        assert_eq!(
+            (loaded.total_sips_today, loaded.total_amount_today),
+            (1, 150)
+        );
+        let day = |d| NaiveDate::from_ymd_opt(2025, 6, d).unwrap();
+        let days: Vec<_> = repo
+            .daily_totals(day(10), day(11))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|total| (total.date, total.total_ml))
+            .collect();
+        assert_eq!(days, vec![(day(10), 100), (day(11), 150)]);
+    }
+
+    #[tokio::test]
+    async fn read_from_db_without_sips_starts_empty() {
+        let (repo, clock, _) = setup().await;
+        let state = SipState::default().read_from_db(&repo, &clock).await;
+
+        assert_eq!(state.totals(), (None, 0, 0, 0, 0));
+        assert_eq!(state.today, Some(clock.local_now().date_naive()));
+        assert_eq!(state.last_sip_at(), None);
+    }
+
+    #[tokio::test]
+    async fn set_notified_user_is_stored_on_the_latest_sip() {
+        let (repo, clock, session_id) = setup().await;
+        let mut state = SipState::default()
+            .take_sip(100, origin(session_id, None), &repo, &clock)
+            .await
+            .unwrap();
+
+        state.set_notified_user(true, &repo).await.unwrap();
+        assert!(state.notified_user);
+        assert!(
+            SipState::default()
+                .read_from_db(&repo, &clock)
+                .await
+                .notified_user
+        );
+
+        state.set_notified_user(false, &repo).await.unwrap();
+        let stored = repo.sip(state.last_sip_id.unwrap()).await.unwrap().unwrap();
+        assert!(!stored.notified_user);
+    }
+
+    #[tokio::test]
+    async fn set_notified_user_without_sips_is_kept_in_memory() {
+        let (repo, clock, session_id) = setup().await;
+        let mut state = SipState::default();
+
+        state.set_notified_user(true, &repo).await.unwrap();
+        assert!(state.notified_user);
+
+        // The first sip answers it
+        let state = state
+            .take_sip(100, origin(session_id, None), &repo, &clock)
+            .await
+            .unwrap();
+        assert!(!state.notified_user);
+    }
+
+    /// The totals computed the slow way, from every stored sip
+    async fn scanned_totals(
+        repo: &SqliteRepository,
+        clock: &dyn Clock,
+    ) -> (Option<i64>, i64, i64, i64, i64) {
+        let sips = repo.all_sips().await.unwrap();
+        let today = clock.local_now().date_naive();
+        let today_sips: Vec<_> = sips
+            .iter()
+            .filter(|sip| sip.created_at.with_timezone(&clock.zone()).date_naive() == today)
+            .collect();
+        let last_sip = sips.iter().max_by_key(|sip| (sip.created_at, sip.id));
+
+        (
+            last_sip.map(|sip| sip.id),
+            today_sips.len() as i64,
+            today_sips.iter().map(|sip| sip.amount).sum(),
+            sips.len() as i64,
+            sips.iter().map(|sip| sip.amount).sum(),
+        )
+    }
+
+    #[tokio::test]
+    async fn aggregated_totals_match_a_full_scan() {
+        let (repo, clock, session_id) = setup().await;
+        // A sip every 17 minutes over the last five days
+        for n in 1..=420 {
+            let at = clock.local_now() - TimeDelta::minutes(n * 17);
+            repo.insert_sip(25 + n % 200, session_id, None, at, SipSource::Tray, None)
+                .await
+                .unwrap();
+        }
+
+        let mut state = SipState::default().read_from_db(&repo, &clock).await;
+        assert_eq!(state.totals(), scanned_totals(&repo, &clock).await);
+
+        for amount in [50, 75, 100] {
+            clock.advance(TimeDelta::minutes(3));
+            state = state
+                .take_sip(amount, origin(session_id, None), &repo, &clock)
+                .await
+                .unwrap();
+        }
+        assert_eq!(state.totals(), scanned_totals(&repo, &clock).await);
+
+        assert!(state.reconcile(&repo, &clock).await);
+        assert_eq!(state.totals(), scanned_totals(&repo, &clock).await);
+    }
+
+    #[tokio::test]
+    async fn reconcile_replaces_drifted_totals() {
+        let (repo, clock, session_id) = setup().await;
+        let mut state = SipState::default();
+        for amount in [100, 200] {
+            state = state
+                .take_sip(amount, origin(session_id, None), &repo, &clock)
+                .await
+                .unwrap();
+        }
+
+        sqlx::query("UPDATE sips SET amount = amount + 1")
+            .execute(repo.pool())
+            .await
+            .unwrap();
+        assert_ne!(state.totals(), scanned_totals(&repo, &clock).await);
+
+        assert!(!state.reconcile(&repo, &clock).await);
+        assert_eq!(state.totals(), scanned_totals(&repo, &clock).await);
+        assert!(state.reconcile(&repo, &clock).await);
+    }
+
+    #[tokio::test]
+    async fn a_merged_request_is_found_by_its_id() {
+        let (repo, clock, session_id) = setup().await;
+        let state = SipState::default()
+            .take_sip(100, origin(session_id, Some("first")), &repo, &clock)
+            .await
+            .unwrap();
+
+        let merged = state
+            .merge_sip(50, Some("retried"), &repo, &clock)
+            .await
+            .unwrap();
+        assert_eq!(merged.total_amount_today, 150);
+
+        let found = repo.sip_by_request_id("retried").await.unwrap().unwrap();
+        assert_eq!(found.id, state.last_sip_id.unwrap());
+        assert_eq!(found.amount, 150);
+
+        // A retry that slips past the lookup still can't merge twice
+        assert!(merged
+            .merge_sip(50, Some("retried"), &repo, &clock)
+            .await
+            .is_err());
+        let stored = repo.sip(found.id).await.unwrap().unwrap();
+        assert_eq!(stored.amount, 150);
+    }
+}
@@ -0,0 +1,239 @@
+use std::future::Future;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite};
+
+use crate::{
+    db::local_day_range,
+    repository::SqliteRepository,
+    sip::{Sip, SipSource},
+};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// Filters and position for browsing the sip history, newest first
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SipQuery {
+    /// First local day to include
+    pub from: Option<NaiveDate>,
+    /// Last local day to include
+    pub to: Option<NaiveDate>,
+    pub session_id: Option<i64>,
+    /// Only sips logged during this timer run
+    pub run_id: Option<i64>,
+    pub source: Option<SipSource>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SipPage {
+    pub sips: Vec<Sip>,
+    /// Pass back as `cursor` to get the next page, `None` on the last page
+    pub next_cursor: Option<i64>,
+    /// Number of sips matching the filters across all pages
+    pub total_count: i64,
+}
+
+impl SipQuery {
+    pub fn page_size(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(anyhow::anyhow!(
+                    "Range start {} is after its end {}",
+                    from,
+                    to
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Appends the filters, without the cursor, as a WHERE clause
+    fn push_filters(&self, builder: &mut QueryBuilder<'_, Sqlite>) {
+        builder.push(" WHERE 1 = 1");
+
+        if let Some(from) = self.from {
+            let (start, _) = local_day_range(from, from);
+            builder.push(" AND created_at_local >= ").push_bind(start);
+        }
+        if let Some(to) = self.to {
+            let (_, end) = local_day_range(to, to);
+            builder.push(" AND created_at_local < ").push_bind(end);
+        }
+        if let Some(session_id) = self.session_id {
+            builder.push(" AND session_id = ").push_bind(session_id);
+        }
+        if let Some(run_id) = self.run_id {
+            builder.push(" AND run_id = ").push_bind(run_id);
+        }
+        if let Some(source) = self.source {
+            builder.push(" AND source = ").push_bind(source.as_str());
+        }
+    }
+}
+
+/// Reading the sip history a page at a time
+pub trait SipQueryRepository {
+    /// Fails if the cursor's sip no longer exists
+    fn query_sips(&self, query: &SipQuery) -> impl Future<Output = anyhow::Result<SipPage>> + Send;
+
+    /// The latest `limit` sips, newest first
+    fn recent_sips(&self, limit: u32)
+        -> impl Future<Output = Result<Vec<Sip>, sqlx::Error>> + Send;
+}
+
+impl SipQueryRepository for SqliteRepository {
+    async fn query_sips(&self, query: &SipQuery) -> anyhow::Result<SipPage> {
+        let page_size = query.page_size();
+
+        // Without the cursor's sip there is no telling where the page starts
+        let cursor = match query.cursor {
+            Some(cursor) => {
+                let created_at =
+                    sqlx::query_scalar::<_, String>("SELECT created_at FROM sips WHERE id = ?")
+                        .bind(cursor)
+                        .fetch_optional(self.pool())
+                        .await?
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Invalid cursor: sip {} no longer exists", cursor)
+                        })?;
+                Some((created_at, cursor))
+            }
+            None => None,
+        };
+
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM sips");
+        query.push_filters(&mut count);
+        let total_count = count
+            .build_query_scalar::<i64>()
+            .fetch_one(self.pool())
+            .await?;
+
+        let mut select = QueryBuilder::new("SELECT * FROM sips");
+        query.push_filters(&mut select);
+        if let Some((created_at, id)) = cursor {
+            // Keyset pagination: continue right after the cursor's sip
+            select
+                .push(" AND (created_at, id) < (")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(id)
+                .push(")");
+        }
+        // Fetch one extra row to learn whether another page follows
+        select
+            .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+            .push_bind(i64::from(page_size) + 1);
+
+        let mut sips = select
+            .build_query_as::<Sip>()
+            .fetch_all(self.pool())
+            .await?;
+
+        let next_cursor = if sips.len() > page_size as usize {
+            sips.truncate(page_size as usize);
+            sips.last().map(|sip| sip.id)
+        } else {
+            None
+        };
+
+        Ok(SipPage {
+            sips,
+            next_cursor,
+            total_count,
+        })
+    }
+
+    async fn recent_sips(&self, limit: u32) -> Result<Vec<Sip>, sqlx::Error> {
+        // Walks idx_sips_created_at backwards, which also orders by rowid
+        sqlx::query_as::<_, Sip>("SELECT * FROM sips ORDER BY created_at DESC, id DESC LIMIT ?")
+            .bind(limit.clamp(1, MAX_PAGE_SIZE))
+            .fetch_all(self.pool())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use super::*;
+    use crate::{
+        clock::LocalZone,
+        db::Database,
+        repository::{SessionRepository, SipRepository},
+    };
+
+    async fn seeded(count: i64) -> SqliteRepository {
+        let db = Database::in_memory().await.unwrap();
+        let repo = SqliteRepository::new(db.pool);
+        let start: DateTime<Utc> = "2025-06-10T08:00:00Z".parse().unwrap();
+        let session_id = repo.insert_session("test", start).await.unwrap();
+
+        for i in 0..count {
+            // Pairs of sips share a timestamp so the id has to break the tie
+            let at = start + TimeDelta::minutes(i / 2);
+            let at = at.with_timezone(&LocalZone::from(chrono_tz::UTC));
+            repo.insert_sip(100, session_id, None, at, SipSource::Tray, None)
+                .await
+                .unwrap();
+        }
+        repo
+    }
+
+    fn page(cursor: Option<i64>) -> SipQuery {
+        SipQuery {
+            cursor,
+            limit: Some(3),
+            ..SipQuery::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_cover_every_sip_once() {
+        let repo = seeded(8).await;
+        let mut ids = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = repo.query_sips(&page(cursor)).await.unwrap();
+            assert_eq!(page.total_count, 8);
+            ids.extend(page.sips.iter().map(|sip| sip.id));
+
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(ids, (1..=8).rev().collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn a_deleted_cursor_is_an_error() {
+        let repo = seeded(8).await;
+        let first = repo.query_sips(&page(None)).await.unwrap();
+        let cursor = first.next_cursor.unwrap();
+
+        sqlx::query("DELETE FROM sips WHERE id = ?")
+            .bind(cursor)
+            .execute(repo.pool())
+            .await
+            .unwrap();
+
+        let error = repo.query_sips(&page(Some(cursor))).await.unwrap_err();
+        assert!(error.to_string().contains("Invalid cursor"));
+    }
+}
@@ -0,0 +1,971 @@
+use std::sync::Arc;
+
+use chrono::TimeDelta;
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot, watch};
+
+use crate::{
+    app_state::AppState,
+    clock::Clock,
+    intake::{check_intake, IntakeCheck, IntakeLimits},
+    message::MessageId,
+    presence::{AwayChange, AwayTracker, Presence},
+    repository::{DailyTotalsRepository, SessionRepository, SipRepository},
+    schedule::ReminderPolicy,
+    settings::{AppSettings, PartialAppSettings, TimerAutoStart},
+    sip::{SipOrigin, SipSource, SipState},
+};
+
+/// How many requests may queue up before senders wait
+const REQUEST_BUFFER: usize = 64;
+
+/// All mutable domain state, as owned by the actor
+#[derive(Debug, Clone, Serialize)]
+pub struct StateSnapshot {
+    pub settings: AppSettings,
+    pub app_state: AppState,
+    pub sip_state: SipState,
+}
+
+/// Outcome of asking to log a sip
+#[derive(Debug, Clone)]
+pub enum LoggedSip {
+    /// Stored as a new sip, or added to the previous one if `merged`
+    Logged {
+        sip_state: SipState,
+        sip_id: Option<i64>,
+        merged: bool,
+    },
+    /// The request id was stored before, nothing changed
+    AlreadyLogged(SipState),
+    /// The sip goes over an intake limit or follows the previous one too
+    /// closely and was not logged
+    NeedsConfirmation(IntakeCheck),
+}
+
+/// What the reminder loop has to do after a tick
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tick {
+    /// A new local day started and the totals were reset
+    pub new_day: bool,
+    /// A reminder should be shown now
+    pub reminder_due: bool,
+    /// Working hours began and the timer was started for them
+    pub timer_started: bool,
+    /// The user came back after being away this long and a sip is due, so a
+    /// single welcome back reminder should be shown instead of `reminder_due`
+    pub welcome_back: Option<TimeDelta>,
+    /// A snooze ran out and the timer state changed
+    pub snooze_ended: bool,
+    /// Which reminder in a row since the latest sip this one is, starting at 1
+    pub reminder_step: u32,
+}
+
+impl Tick {
+    /// The reminder to show, a welcome back taking the place of the regular one
+    pub fn reminder(&self) -> Option<MessageId> {
+        if self.welcome_back.is_some() {
+            Some(MessageId::WelcomeBack)
+        } else if self.reminder_due {
+            Some(MessageId::Sip)
+        } else {
+            None
+        }
+    }
+}
+
+enum Request {
+    UpdateSettings {
+        partial: Box<PartialAppSettings>,
+        reply: oneshot::Sender<anyhow::Result<AppSettings>>,
+    },
+    ToggleTimer {
+        reply: oneshot::Sender<anyhow::Result<AppState>>,
+    },
+    CheckSip {
+        amount_ml: Option<i64>,
+        reply: oneshot::Sender<anyhow::Result<IntakeCheck>>,
+    },
+    LogSip {
+        amount_ml: Option<i64>,
+        source: SipSource,
+        request_id: Option<String>,
+        confirmed: bool,
+        reply: oneshot::Sender<anyhow::Result<LoggedSip>>,
+    },
+    Tick {
+        reply: oneshot::Sender<Tick>,
+    },
+    MarkNotified {
+        reply: oneshot::Sender<anyhow::Result<()>>,
+    },
+    Snooze {
+        minutes: i64,
+        reply: oneshot::Sender<AppState>,
+    },
+}
+
+/// Owns the settings, the timer and the sip totals. Requests are handled one
+/// at a time, so no caller ever needs a lock, and every change is published
+/// as a fresh [`StateSnapshot`].
+pub struct StateActor<R> {
+    state: StateSnapshot,
+    repo: R,
+    clock: Arc<dyn Clock>,
+    requests: mpsc::Receiver<Request>,
+    snapshots: watch::Sender<StateSnapshot>,
+    /// Whether the previous tick fell within working hours
+    in_working_hours: bool,
+    presence: watch::Receiver<Presence>,
+    away: AwayTracker,
+    /// Reminders shown since the latest sip
+    unanswered_reminders: u32,
+}
+
+/// Cheap to clone access to the actor
+#[derive(Clone)]
+pub struct StateHandle {
+    requests: mpsc::Sender<Request>,
+    snapshots: watch::Receiver<StateSnapshot>,
+}
+
+impl<R> StateActor<R>
+where
+    R: SipRepository + DailyTotalsRepository + SessionRepository + Send + Sync + 'static,
+{
+    /// Reminders are held while `presence` reports the user away, see
+    /// [`AppSettings::pause_when_away`]
+    pub fn new(
+        state: StateSnapshot,
+        repo: R,
+        clock: Arc<dyn Clock>,
+        presence: watch::Receiver<Presence>,
+    ) -> (Self, StateHandle) {
+        let (request_tx, request_rx) = mpsc::channel(REQUEST_BUFFER);
+        let (snapshot_tx, snapshot_rx) = watch::channel(state.clone());
+        let in_working_hours = state.settings.is_working_hours(clock.local_now().time());
+
+        let actor = Self {
+            state,
+            repo,
+            clock,
+            requests: request_rx,
+            snapshots: snapshot_tx,
+            in_working_hours,
+            presence,
+            away: AwayTracker::default(),
+            unanswered_reminders: 0,
+        };
+        let handle = StateHandle {
+            requests: request_tx,
+            snapshots: snapshot_rx,
+        };
+
+        (actor, handle)
+    }
+
+    /// Serves requests until every handle is dropped
+    pub async fn run(mut self) {
+        while let Some(request) = self.requests.recv().await {
+            if self.handle(request).await {
+                self.snapshots.send_replace(self.state.clone());
+            }
+        }
+    }
+
+    /// Handles one request, returning whether the state changed
+    async fn handle(&mut self, request: Request) -> bool {
+        match request {
+            Request::UpdateSettings { partial, reply } => {
+                let result = self.update_settings(*partial).await;
+                let changed = result.is_ok();
+                let _ = reply.send(result);
+                changed
+            }
+            Request::ToggleTimer { reply } => {
+                let result = self.toggle_timer().await;
+                let changed = result.is_ok();
+                let _ = reply.send(result);
+                changed
+            }
+            Request::CheckSip { amount_ml, reply } => {
+                let amount_ml = amount_ml.unwrap_or(self.state.settings.sip_amount_ml);
+                let _ = reply.send(self.check_sip(amount_ml).await);
+                false
+            }
+            Request::LogSip {
+                amount_ml,
+                source,
+                request_id,
+                confirmed,
+                reply,
+            } => {
+                let amount_ml = amount_ml.unwrap_or(self.state.settings.sip_amount_ml);
+                let result = self
+                    .log_sip(amount_ml, source, request_id.as_deref(), confirmed)
+                    .await;
+                let changed = matches!(result, Ok(LoggedSip::Logged { .. }));
+                let _ = reply.send(result);
+                changed
+            }
+            Request::Tick { reply } => {
+                let tick = self.tick().await;
+                let _ = reply.send(tick);
+                tick.new_day || tick.timer_started || tick.snooze_ended
+            }
+            Request::Snooze { minutes, reply } => {
+                let app_state = &mut self.state.app_state;
+                app_state.snoozed_until = Some(self.clock.now() + TimeDelta::minutes(minutes));
+                let _ = reply.send(app_state.clone());
+                true
+            }
+            Request::MarkNotified { reply } => {
+                let result = self
+                    .state
+                    .sip_state
+                    .set_notified_user(true, &self.repo)
+                    .await;
+                let changed = result.is_ok();
+                if changed {
+                    self.unanswered_reminders += 1;
+                }
+                let _ = reply.send(result);
+                changed
+            }
+        }
+    }
+
+    async fn update_settings(
+        &mut self,
+        partial: PartialAppSettings,
+    ) -> anyhow::Result<AppSettings> {
+        // Work on a copy so a rejected update leaves the settings untouched
+        let mut settings = self.state.settings.clone();
+        settings.update_with_partial(partial)?;
+
+        // The daily totals triggers stamp new days with the goal stored in the
+        // database, so it is updated before anything else can see the change
+        let today = self.clock.local_now().date_naive();
+        self.repo
+            .set_daily_goal(settings.daily_goal_ml, today)
+            .await?;
+
+        if let Err(e) = settings.save() {
+            let previous_goal = self.state.settings.daily_goal_ml;
+            if let Err(rollback) = self.repo.set_daily_goal(previous_goal, today).await {
+                eprintln!("Failed to restore the daily goal: {}", rollback);
+            }
+            return Err(e);
+        }
+
+        self.state.settings = settings.clone();
+        Ok(settings)
+    }
+
+    async fn toggle_timer(&mut self) -> anyhow::Result<AppState> {
+        // Work on a copy so a failed write leaves the timer as it was
+        let mut app_state = self.state.app_state.clone();
+        if app_state.timer_started {
+            app_state
+                .stop_timer(&self.repo, self.clock.as_ref())
+                .await?;
+        } else {
+            app_state
+                .start_timer(&self.repo, self.clock.as_ref())
+                .await?;
+        }
+
+        self.state.app_state = app_state.clone();
+        Ok(app_state)
+    }
+
+    /// Checks whether a sip of `amount_ml` needs confirmation, either
+    /// because it follows the previous one too closely or goes over an
+    /// intake limit
+    async fn check_sip(&self, amount_ml: i64) -> anyhow::Result<IntakeCheck> {
+        let settings = &self.state.settings;
+        let sip_state = &self.state.sip_state;
+        let now = self.clock.now();
+
+        // Duplicates that get merged don't need to be confirmed
+        if !settings.merge_duplicate_sips {
+            if let Some(seconds_since_last) =
+                sip_state.recent_sip(now, settings.min_sip_gap_seconds)
+            {
+                return Ok(IntakeCheck::TooSoonAfterLast {
+                    seconds_since_last,
+                    min_gap_seconds: settings.min_sip_gap_seconds,
+                });
+            }
+        }
+
+        let check = check_intake(
+            &self.repo,
+            &IntakeLimits::from_settings(settings),
+            amount_ml,
+            sip_state.total_amount_today,
+            now,
+        )
+        .await?;
+
+        Ok(check)
+    }
+
+    async fn log_sip(
+        &mut self,
+        amount_ml: i64,
+        source: SipSource,
+        request_id: Option<&str>,
+        confirmed: bool,
+    ) -> anyhow::Result<LoggedSip> {
+        if amount_ml <= 0 {
+            return Err(anyhow::anyhow!(
+                "Sip amount must be positive, got {}ml",
+                amount_ml
+            ));
+        }
+
+        let session_id = self
+            .state
+            .app_state
+            .session_id
+            .ok_or_else(|| anyhow::anyhow!("No session ID available"))?;
+
+        // A retried request gets the current state back without a second sip
+        if let Some(request_id) = request_id {
+            if self.repo.sip_by_request_id(request_id).await?.is_some() {
+                return Ok(LoggedSip::AlreadyLogged(self.state.sip_state.clone()));
+            }
+        }
+
+        if !confirmed {
+            let check = self.check_sip(amount_ml).await?;
+            if !check.is_within_limits() {
+                return Ok(LoggedSip::NeedsConfirmation(check));
+            }
+        }
+
+        let settings = &self.state.settings;
+        let sip_state = &self.state.sip_state;
+        let merged = settings.merge_duplicate_sips
+            && sip_state
+                .recent_sip(self.clock.now(), settings.min_sip_gap_seconds)
+                .is_some();
+
+        let new_state = if merged {
+            sip_state
+                .merge_sip(amount_ml, request_id, &self.repo, self.clock.as_ref())
+                .await?
+        } else {
+            sip_state
+                .take_sip(
+                    amount_ml,
+                    SipOrigin {
+                        session_id,
+                        run_id: self.state.app_state.run_id,
+                        source,
+                        request_id,
+                    },
+                    &self.repo,
+                    self.clock.as_ref(),
+                )
+                .await?
+        };
+        self.state.sip_state = new_state.clone();
+        // Drinking answers the reminder that was snoozed
+        self.state.app_state.snoozed_until = None;
+        self.unanswered_reminders = 0;
+
+        Ok(LoggedSip::Logged {
+            sip_id: new_state.last_sip_id,
+            sip_state: new_state,
+            merged,
+        })
+    }
+
+    async fn tick(&mut self) -> Tick {
+        let timer_started = self.start_for_working_hours().await;
+
+        let clock = self.clock.as_ref();
+        let sip_state = &mut self.state.sip_state;
+
+        let new_day = sip_state.roll_over(clock);
+        if new_day {
+            // A new day is a cheap moment to double check the incremental totals
+            sip_state.reconcile(&self.repo, clock).await;
+        }
+
+        let settings = &self.state.settings;
+        let policy = ReminderPolicy::from_settings(settings);
+        // With notifications turned off there is nobody to remind
+        let sip_due = settings.notifications_enabled
+            && self.state.app_state.timer_started
+            && sip_state.check_if_sip_is_due(&policy, clock.local_now());
+        let notified_user = sip_state.notified_user;
+
+        let (snoozed, snooze_ended) = self.check_snooze();
+        let mut tick = Tick {
+            new_day,
+            timer_started,
+            snooze_ended,
+            ..Tick::default()
+        };
+
+        match self.update_away() {
+            // Snoozing holds every reminder, the away tracker still follows along
+            _ if snoozed => {}
+            // Whatever was missed while away is covered by a single reminder
+            AwayChange::CameBack { away_for } => {
+                if sip_due && !self.over_hourly_limit().await {
+                    tick.welcome_back = Some(away_for);
+                }
+            }
+            _ if self.away.is_away() => {}
+            _ => {
+                // A reminder that was snoozed is shown again once the snooze ends
+                tick.reminder_due =
+                    sip_due && (!notified_user || snooze_ended) && !self.over_hourly_limit().await;
+            }
+        }
+
+        if tick.reminder_due || tick.welcome_back.is_some() {
+            tick.reminder_step = self.unanswered_reminders + 1;
+        }
+
+        tick
+    }
+
+    /// Whether reminders are snoozed right now, and whether a snooze just ran out
+    fn check_snooze(&mut self) -> (bool, bool) {
+        let app_state = &mut self.state.app_state;
+        match app_state.snoozed_until {
+            Some(until) if self.clock.now() < until => (true, false),
+            Some(_) => {
+                app_state.snoozed_until = None;
+                (false, true)
+            }
+            None => (false, false),
+        }
+    }
+
+    /// Feeds the latest presence to the away tracker. With pausing turned
+    /// off the user never counts as away.
+    fn update_away(&mut self) -> AwayChange {
+        let settings = &self.state.settings;
+        let presence = if settings.pause_when_away {
+            *self.presence.borrow()
+        } else {
+            Presence::Active
+        };
+
+        let change = self.away.update(
+            presence,
+            TimeDelta::minutes(settings.away_idle_minutes),
+            self.clock.now(),
+        );
+        match change {
+            AwayChange::WentAway => println!("User is away, holding reminders"),
+            AwayChange::CameBack { away_for } => {
+                println!("User is back after {} minutes", away_for.num_minutes())
+            }
+            AwayChange::Unchanged => {}
+        }
+
+        change
+    }
+
+    /// Starts the timer when working hours begin, if the settings ask for it
+    /// and it isn't running already
+    async fn start_for_working_hours(&mut self) -> bool {
+        let settings = &self.state.settings;
+        let in_working_hours = settings.is_working_hours(self.clock.local_now().time());
+        let began = in_working_hours && !self.in_working_hours;
+        self.in_working_hours = in_working_hours;
+
+        if !began
+            || settings.timer_auto_start != TimerAutoStart::WorkingHours
+            || self.state.app_state.timer_started
+        {
+            return false;
+        }
+
+        match self.toggle_timer().await {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("Failed to start timer for working hours: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn over_hourly_limit(&self) -> bool {
+        let limits = IntakeLimits::from_settings(&self.state.settings);
+
+        match self
+            .repo
+            .amount_since(IntakeLimits::window_start(self.clock.now()))
+            .await
+        {
+            Ok(window_ml) if limits.is_over_hourly(window_ml) => {
+                println!("Over the hourly intake limit, holding the reminder");
+                true
+            }
+            Ok(_) => false,
+            Err(e) => {
+                eprintln!("Failed to check hourly intake: {}", e);
+                false
+            }
+        }
+    }
+}
+
+impl StateHandle {
+    /// The latest published state, without waiting on the actor
+    pub fn snapshot(&self) -> StateSnapshot {
+        self.snapshots.borrow().clone()
+    }
+
+    /// Notified whenever a request changes the state
+    pub fn subscribe(&self) -> watch::Receiver<StateSnapshot> {
+        self.snapshots.clone()
+    }
+
+    async fn request<T>(
+        &self,
+        request: impl FnOnce(oneshot::Sender<T>) -> Request,
+    ) -> anyhow::Result<T> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.requests
+            .send(request(reply_tx))
+            .await
+            .map_err(|_| anyhow::anyhow!("State actor is not running"))?;
+
+        reply_rx
+            .await
+            .map_err(|_| anyhow::anyhow!("State actor dropped the request"))
+    }
+
+    pub async fn update_settings(
+        &self,
+        partial: PartialAppSettings,
+    ) -> anyhow::Result<AppSettings> {
+        self.request(|reply| Request::UpdateSettings {
+            partial: Box::new(partial),
+            reply,
+        })
+        .await?
+    }
+
+    /// Starts or stops the timer, opening or closing a timer run
+    pub async fn toggle_timer(&self) -> anyhow::Result<AppState> {
+        self.request(|reply| Request::ToggleTimer { reply }).await?
+    }
+
+    /// Whether a sip of `amount_ml`, or of the configured amount if not
+    /// given, would need confirmation
+    pub async fn check_sip(&self, amount_ml: Option<i64>) -> anyhow::Result<IntakeCheck> {
+        self.request(|reply| Request::CheckSip { amount_ml, reply })
+            .await?
+    }
+
+    /// Logs a sip of `amount_ml`, or of the configured amount if not given.
+    /// Unless `confirmed` is set, sips over an intake limit or right after the
+    /// previous one are handed back for confirmation. A `request_id` that was
+    /// already stored is not logged again.
+    pub async fn log_sip(
+        &self,
+        amount_ml: Option<i64>,
+        source: SipSource,
+        request_id: Option<String>,
+        confirmed: bool,
+    ) -> anyhow::Result<LoggedSip> {
+        self.request(|reply| Request::LogSip {
+            amount_ml,
+            source,
+            request_id,
+            confirmed,
+            reply,
+        })
+        .await?
+    }
+
+    /// Advances the day if needed and reports whether a reminder is due
+    pub async fn tick(&self) -> anyhow::Result<Tick> {
+        self.request(|reply| Request::Tick { reply }).await
+    }
+
+    /// Holds reminders for `minutes`, after which the last one is shown again
+    pub async fn snooze(&self, minutes: i64) -> anyhow::Result<AppState> {
+        self.request(|reply| Request::Snooze { minutes, reply })
+            .await
+    }
+
+    /// Records that the user was reminded about the latest sip
+    pub async fn mark_notified(&self) -> anyhow::Result<()> {
+        self.request(|reply| Request::MarkNotified { reply })
+            .await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+    use crate::{
+        audio::{AudioAlerts, NullAudioSink, ReminderSounds},
+        clock::ManualClock,
+        db::Database,
+        goal::next_goal_notification,
+        message::{Catalog, MessageContext, Messages},
+        notifier::{
+            Notification, NotificationChannel, NotificationKind, Notifiers, RecordingNotifier,
+        },
+        repository::SqliteRepository,
+    };
+
+    struct Harness {
+        handle: StateHandle,
+        repo: SqliteRepository,
+        clock: Arc<ManualClock>,
+        presence: watch::Sender<Presence>,
+    }
+
+    impl Harness {
+        /// A running actor with the timer started and a sip taken at 08:00
+        async fn start(settings: AppSettings) -> Self {
+            let harness = Self::without_sips(settings).await;
+            harness
+                .handle
+                .log_sip(Some(100), SipSource::MainWindow, None, true)
+                .await
+                .unwrap();
+            harness
+        }
+
+        /// A running actor with the timer started at 08:00 and no sips yet
+        async fn without_sips(settings: AppSettings) -> Self {
+            let start: DateTime<Utc> = "2025-06-10T08:00:00Z".parse().unwrap();
+            let clock = Arc::new(ManualClock::new(start));
+            let repo = SqliteRepository::new(Database::in_memory().await.unwrap().pool);
+
+            let mut app_state = AppState::new(clock.as_ref());
+            app_state.init_session(&repo).await.unwrap();
+            app_state.start_timer(&repo, clock.as_ref()).await.unwrap();
+            let sip_state = SipState::default()
+                .read_from_db(&repo, clock.as_ref())
+                .await;
+
+            let (presence, presence_rx) = watch::channel(Presence::Active);
+            let state = StateSnapshot {
+                settings,
+                app_state,
+                sip_state,
+            };
+            let (actor, handle) = StateActor::new(state, repo.clone(), clock.clone(), presence_rx);
+            tokio::spawn(actor.run());
+
+            Self {
+                handle,
+                repo,
+                clock,
+                presence,
+            }
+        }
+
+        /// Renders message `id` and hands it to `notifiers`, as the app does
+        /// for reminders, goals and achievements
+        fn deliver(
+            &self,
+            notifiers: &Notifiers,
+            id: MessageId,
+            step: u32,
+            fill: impl FnOnce(&mut MessageContext),
+        ) -> Option<NotificationChannel> {
+            let snapshot = self.handle.snapshot();
+            let settings = snapshot.settings;
+            let mut context =
+                MessageContext::new(&settings, &snapshot.sip_state, 0, self.clock.local_now());
+            fill(&mut context);
+
+            let messages = Messages::new(
+                Catalog::for_locale("en"),
+                settings.message_templates.clone(),
+            );
+            let alerts = AudioAlerts::new(
+                Arc::new(NullAudioSink::new()),
+                ReminderSounds::default(),
+                100,
+            );
+            let notification = messages.render(id, &context).with_step(step);
+            notifiers
+                .deliver(&notification, &settings, &alerts)
+                .unwrap()
+        }
+
+        /// Ticks after `minutes` and shows the reminder it calls for, the way
+        /// the reminder loop does
+        async fn remind_after(&self, minutes: i64, notifiers: &Notifiers) {
+            let tick = self.tick_after(minutes).await;
+            if let Some(id) = tick.reminder() {
+                self.deliver(notifiers, id, tick.reminder_step, |context| {
+                    if let Some(away_for) = tick.welcome_back {
+                        context.away_minutes = away_for.num_minutes();
+                    }
+                });
+            }
+        }
+
+        /// Logs a sip and announces the goal milestone it reached, if any
+        async fn drink(&self, amount_ml: i64, notifiers: &Notifiers) {
+            self.handle
+                .log_sip(Some(amount_ml), SipSource::MainWindow, None, true)
+                .await
+                .unwrap();
+
+            let settings = self.handle.snapshot().settings;
+            let sip_state = self.handle.snapshot().sip_state;
+            let progress = next_goal_notification(
+                &self.repo,
+                self.clock.local_now().date_naive(),
+                sip_state.total_amount_today,
+                settings.daily_goal_ml,
+                settings.goal_milestone_notifications,
+                self.clock.now(),
+            )
+            .await
+            .unwrap();
+
+            if let Some(progress) = progress {
+                self.deliver(notifiers, progress.message(), 1, |context| {
+                    if progress.message() == MessageId::GoalMilestone {
+                        context.percent = i64::from(progress.percent);
+                    }
+                });
+            }
+        }
+
+        /// Moves time on and ticks, marking reminders as shown like the
+        /// reminder loop does
+        async fn tick_after(&self, minutes: i64) -> Tick {
+            self.clock.advance(TimeDelta::minutes(minutes));
+            let tick = self.handle.tick().await.unwrap();
+            if tick.reminder_due || tick.welcome_back.is_some() {
+                self.handle.mark_notified().await.unwrap();
+            }
+            tick
+        }
+    }
+
+    fn settings() -> AppSettings {
+        AppSettings {
+            notification_interval_minutes: 10,
+            pause_when_away: true,
+            away_idle_minutes: 5,
+            ..AppSettings::default()
+        }
+    }
+
+    fn reminders(ticks: &[Tick]) -> (usize, usize) {
+        (
+            ticks.iter().filter(|tick| tick.reminder_due).count(),
+            ticks
+                .iter()
+                .filter(|tick| tick.welcome_back.is_some())
+                .count(),
+        )
+    }
+
+    #[tokio::test]
+    async fn locked_session_holds_reminders_until_one_welcome_back() {
+        let harness = Harness::start(settings()).await;
+        harness.presence.send_replace(Presence::Locked);
+
+        let mut away = Vec::new();
+        for _ in 0..6 {
+            away.push(harness.tick_after(10).await);
+        }
+        assert_eq!(reminders(&away), (0, 0));
+
+        // Away from the first tick that saw the lock
+        harness.presence.send_replace(Presence::Active);
+        let back = harness.tick_after(1).await;
+        assert_eq!(back.welcome_back, Some(TimeDelta::minutes(51)));
+        assert!(!back.reminder_due);
+        assert_eq!(back.reminder_step, 1);
+
+        let mut after = Vec::new();
+        for _ in 0..5 {
+            after.push(harness.tick_after(1).await);
+        }
+        assert_eq!(reminders(&after), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn long_idle_holds_reminders_and_short_idle_does_not() {
+        let harness = Harness::start(settings()).await;
+
+        // Idle for two minutes when the reminder comes due
+        harness.clock.advance(TimeDelta::minutes(9));
+        harness.presence.send_replace(Presence::Idle {
+            since: harness.clock.now(),
+        });
+        assert!(harness.tick_after(2).await.reminder_due);
+
+        let mut away = Vec::new();
+        for _ in 0..4 {
+            away.push(harness.tick_after(10).await);
+        }
+        assert_eq!(reminders(&away), (0, 0));
+
+        harness.presence.send_replace(Presence::Active);
+        let back = harness.tick_after(1).await;
+        assert_eq!(back.welcome_back, Some(TimeDelta::minutes(43)));
+        assert_eq!(back.reminder_step, 2);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_held_with_pausing_turned_off() {
+        let harness = Harness::start(AppSettings {
+            pause_when_away: false,
+            ..settings()
+        })
+        .await;
+        harness.presence.send_replace(Presence::Locked);
+
+        let tick = harness.tick_after(11).await;
+        assert!(tick.reminder_due);
+        assert_eq!(tick.welcome_back, None);
+
+        harness.presence.send_replace(Presence::Active);
+        assert_eq!(harness.tick_after(1).await.welcome_back, None);
+    }
+
+    #[tokio::test]
+    async fn a_reminder_before_the_first_sip_is_shown_once() {
+        let harness = Harness::without_sips(settings()).await;
+
+        let first = harness.tick_after(1).await;
+        assert!(first.reminder_due);
+        assert_eq!(first.reminder_step, 1);
+
+        let mut later = Vec::new();
+        for _ in 0..5 {
+            later.push(harness.tick_after(1).await);
+        }
+        assert_eq!(reminders(&later), (0, 0));
+
+        // Drinking answers it and the next one starts over at the first step
+        harness
+            .handle
+            .log_sip(Some(100), SipSource::MainWindow, None, true)
+            .await
+            .unwrap();
+        let next = harness.tick_after(11).await;
+        assert!(next.reminder_due);
+        assert_eq!(next.reminder_step, 1);
+    }
+
+    #[tokio::test]
+    async fn sips_are_checked_and_logged_with_the_requested_amount() {
+        let harness = Harness::without_sips(AppSettings {
+            max_daily_intake_ml: 1000,
+            ..settings()
+        })
+        .await;
+
+        let check = harness.handle.check_sip(Some(1500)).await.unwrap();
+        assert!(matches!(check, IntakeCheck::OverDailyLimit { .. }));
+        assert!(harness
+            .handle
+            .check_sip(None)
+            .await
+            .unwrap()
+            .is_within_limits());
+
+        let logged = harness
+            .handle
+            .log_sip(Some(250), SipSource::MainWindow, None, false)
+            .await
+            .unwrap();
+        let LoggedSip::Logged { sip_state, .. } = logged else {
+            panic!("Expected the sip to be logged, got {:?}", logged);
+        };
+        assert_eq!(sip_state.total_amount_today, 250);
+
+        assert!(harness
+            .handle
+            .log_sip(Some(0), SipSource::MainWindow, None, true)
+            .await
+            .is_err());
+    }
+
+    fn sent(toast: &RecordingNotifier) -> Vec<(NotificationKind, String, u32)> {
+        toast
+            .sent()
+            .into_iter()
+            .map(
+                |Notification {
+                     kind, title, step, ..
+                 }| (kind, title, step),
+            )
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn ticks_reach_the_notifier() {
+        let toast = RecordingNotifier::new(NotificationChannel::Toast);
+        let notifiers = Notifiers::new().with(toast.clone());
+        let harness = Harness::start(AppSettings {
+            daily_goal_ml: 500,
+            goal_milestone_notifications: false,
+            ..settings()
+        })
+        .await;
+
+        // The reminder goes unanswered, then the user locks the screen
+        harness.remind_after(11, &notifiers).await;
+        harness.remind_after(11, &notifiers).await;
+        harness.presence.send_replace(Presence::Locked);
+        harness.remind_after(1, &notifiers).await;
+        harness.remind_after(30, &notifiers).await;
+        harness.presence.send_replace(Presence::Active);
+        harness.remind_after(1, &notifiers).await;
+
+        harness.drink(400, &notifiers).await;
+
+        assert_eq!(
+            sent(&toast),
+            vec![
+                (NotificationKind::Sip, "Sip is due".to_string(), 1),
+                (NotificationKind::WelcomeBack, "Welcome back".to_string(), 2),
+                (NotificationKind::Goal, "Daily goal reached".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            toast.sent()[1].body,
+            "You were away for 31 minutes, time for a sip"
+        );
+    }
+
+    #[tokio::test]
+    async fn nothing_reaches_the_notifier_with_notifications_off() {
+        let toast = RecordingNotifier::new(NotificationChannel::Toast);
+        let notifiers = Notifiers::new().with(toast.clone());
+        let harness = Harness::start(AppSettings {
+            notifications_enabled: false,
+            daily_goal_ml: 500,
+            ..settings()
+        })
+        .await;
+
+        harness.remind_after(11, &notifiers).await;
+        harness.presence.send_replace(Presence::Locked);
+        harness.remind_after(30, &notifiers).await;
+        harness.presence.send_replace(Presence::Active);
+        harness.remind_after(1, &notifiers).await;
+        harness.drink(400, &notifiers).await;
+
+        assert!(toast.sent().is_empty());
+    }
+}
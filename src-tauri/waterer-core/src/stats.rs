@@ -0,0 +1,591 @@
+use std::future::Future;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+use crate::{
+    db::local_day_range,
+    repository::{DailyTotal, SqliteRepository},
+    sip::SipSource,
+};
+
+/// Longest range a single statistics request may cover
+pub const MAX_RANGE_DAYS: i64 = 3660;
+
+/// Every day between ?1 and ?2, with zeroes for days without sips
+const DAY_SERIES: &str = "
+    WITH RECURSIVE days(date) AS (
+        SELECT date(?1)
+        UNION ALL
+        SELECT date(date, '+1 day') FROM days WHERE date < date(?2)
+    ),
+    series AS (
+        SELECT days.date AS date,
+               COALESCE(totals.total_ml, 0) AS total_ml,
+               COALESCE(totals.sip_count, 0) AS sip_count,
+               COALESCE(totals.goal_ml, (SELECT goal_ml FROM daily_goal WHERE id = 1)) AS goal_ml,
+               COALESCE(totals.goal_met, FALSE) AS goal_met
+        FROM days
+        LEFT JOIN daily_totals AS totals ON totals.date = days.date
+    )";
+
+/// Average intake over a calendar week (starting Monday) or month
+#[derive(Debug, Clone, PartialEq, Serialize, FromRow)]
+pub struct PeriodAverage {
+    /// First day of the period
+    pub period_start: NaiveDate,
+    /// Days of the period that fall inside the requested range
+    pub days: i64,
+    pub total_ml: i64,
+    pub average_ml: f64,
+    pub goal_met_days: i64,
+}
+
+/// Sips in a range logged from one source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, FromRow)]
+pub struct SourceTotal {
+    pub source: SipSource,
+    pub sip_count: i64,
+    pub total_ml: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Statistics {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// One entry per day of the range, including days without sips
+    pub days: Vec<DailyTotal>,
+    pub weekly: Vec<PeriodAverage>,
+    pub monthly: Vec<PeriodAverage>,
+    /// Sips by where they were logged from, largest volume first
+    pub by_source: Vec<SourceTotal>,
+    /// Best and worst of the days that have at least one sip
+    pub best_day: Option<DailyTotal>,
+    pub worst_day: Option<DailyTotal>,
+    pub total_ml: i64,
+    pub average_ml: f64,
+    /// Share of days in the range that met their goal, from 0.0 to 1.0
+    pub goal_completion_rate: f64,
+}
+
+impl Statistics {
+    fn from_series(
+        from: NaiveDate,
+        to: NaiveDate,
+        days: Vec<DailyTotal>,
+        weekly: Vec<PeriodAverage>,
+        monthly: Vec<PeriodAverage>,
+        by_source: Vec<SourceTotal>,
+    ) -> Self {
+        let active_days = days.iter().filter(|day| day.sip_count > 0);
+        let best_day = active_days
+            .clone()
+            .max_by_key(|day| (day.total_ml, day.date))
+            .cloned();
+        let worst_day = active_days
+            .min_by_key(|day| (day.total_ml, day.date))
+            .cloned();
+
+        let total_ml = days.iter().map(|day| day.total_ml).sum::<i64>();
+        let goal_met_days = days.iter().filter(|day| day.goal_met).count();
+        let (average_ml, goal_completion_rate) = match days.len() {
+            0 => (0.0, 0.0),
+            len => (
+                total_ml as f64 / len as f64,
+                goal_met_days as f64 / len as f64,
+            ),
+        };
+
+        Self {
+            from,
+            to,
+            days,
+            weekly,
+            monthly,
+            by_source,
+            best_day,
+            worst_day,
+            total_ml,
+            average_ml,
+            goal_completion_rate,
+        }
+    }
+}
+
+/// Sips logged in one local hour of one weekday
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRow)]
+pub struct HourBucket {
+    /// 0 is Sunday, as in SQLite's `strftime('%w')`
+    pub weekday: i64,
+    pub hour: i64,
+    pub total_ml: i64,
+    pub sip_count: i64,
+}
+
+/// Intake by weekday and local hour. Rows run Monday to Sunday, columns are
+/// the hours 0 to 23.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Heatmap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub volume_ml: [[i64; 24]; 7],
+    pub sip_counts: [[i64; 24]; 7],
+}
+
+impl Heatmap {
+    fn from_buckets(from: NaiveDate, to: NaiveDate, buckets: Vec<HourBucket>) -> Self {
+        let mut volume_ml = [[0; 24]; 7];
+        let mut sip_counts = [[0; 24]; 7];
+
+        for bucket in buckets {
+            let (Ok(weekday), Ok(hour)) = (
+                usize::try_from(bucket.weekday),
+                usize::try_from(bucket.hour),
+            ) else {
+                continue;
+            };
+            if weekday >= 7 || hour >= 24 {
+                continue;
+            }
+
+            // Shift Sunday-first weekdays to Monday-first rows
+            let row = (weekday + 6) % 7;
+            volume_ml[row][hour] += bucket.total_ml;
+            sip_counts[row][hour] += bucket.sip_count;
+        }
+
+        Self {
+            from,
+            to,
+            volume_ml,
+            sip_counts,
+        }
+    }
+}
+
+/// Aggregated views over the sip history
+pub trait StatisticsRepository {
+    /// Per-day totals for every day from `from` to `to`, both inclusive
+    fn daily_series(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<DailyTotal>, sqlx::Error>> + Send;
+
+    fn weekly_averages(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<PeriodAverage>, sqlx::Error>> + Send;
+
+    fn monthly_averages(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<PeriodAverage>, sqlx::Error>> + Send;
+
+    /// Sips between the local days `from` and `to`, both inclusive, bucketed
+    /// by local weekday and hour
+    fn hour_buckets(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<HourBucket>, sqlx::Error>> + Send;
+
+    /// Sips between the local days `from` and `to`, both inclusive, grouped
+    /// by source
+    fn source_totals(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<SourceTotal>, sqlx::Error>> + Send;
+}
+
+impl SqliteRepository {
+    async fn period_averages(
+        &self,
+        period_start: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<PeriodAverage>, sqlx::Error> {
+        let query = format!(
+            "{DAY_SERIES}
+            SELECT {period_start} AS period_start,
+                   COUNT(*) AS days,
+                   SUM(total_ml) AS total_ml,
+                   AVG(total_ml) AS average_ml,
+                   SUM(goal_met) AS goal_met_days
+            FROM series
+            GROUP BY 1
+            ORDER BY 1"
+        );
+
+        sqlx::query_as::<_, PeriodAverage>(&query)
+            .bind(from)
+            .bind(to)
+            .fetch_all(self.pool())
+            .await
+    }
+}
+
+impl StatisticsRepository for SqliteRepository {
+    async fn daily_series(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyTotal>, sqlx::Error> {
+        let query = format!("{DAY_SERIES} SELECT * FROM series ORDER BY date");
+
+        sqlx::query_as::<_, DailyTotal>(&query)
+            .bind(from)
+            .bind(to)
+            .fetch_all(self.pool())
+            .await
+    }
+
+    async fn weekly_averages(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<PeriodAverage>, sqlx::Error> {
+        // 'weekday 0' moves forward to Sunday, six days back from there is Monday
+        self.period_averages("date(date, 'weekday 0', '-6 days')", from, to)
+            .await
+    }
+
+    async fn monthly_averages(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<PeriodAverage>, sqlx::Error> {
+        self.period_averages("date(date, 'start of month')", from, to)
+            .await
+    }
+
+    async fn hour_buckets(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<HourBucket>, sqlx::Error> {
+        let (start, end) = local_day_range(from, to);
+
+        sqlx::query_as::<_, HourBucket>(
+            "SELECT CAST(strftime('%w', created_at_local) AS INTEGER) AS weekday,
+                    CAST(strftime('%H', created_at_local) AS INTEGER) AS hour,
+                    SUM(amount) AS total_ml,
+                    COUNT(*) AS sip_count
+             FROM sips
+             WHERE created_at_local >= ? AND created_at_local < ?
+             GROUP BY 1, 2",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(self.pool())
+        .await
+    }
+
+    async fn source_totals(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<SourceTotal>, sqlx::Error> {
+        let (start, end) = local_day_range(from, to);
+
+        sqlx::query_as::<_, SourceTotal>(
+            "SELECT source, COUNT(*) AS sip_count, SUM(amount) AS total_ml
+             FROM sips
+             WHERE created_at_local >= ? AND created_at_local < ?
+             GROUP BY source
+             ORDER BY total_ml DESC, source",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(self.pool())
+        .await
+    }
+}
+
+/// Collects every aggregate for the range `from` to `to`, both inclusive
+pub async fn statistics(
+    repo: &impl StatisticsRepository,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<Statistics> {
+    validate_range(from, to)?;
+
+    let days = repo.daily_series(from, to).await?;
+    let weekly = repo.weekly_averages(from, to).await?;
+    let monthly = repo.monthly_averages(from, to).await?;
+    let by_source = repo.source_totals(from, to).await?;
+
+    Ok(Statistics::from_series(
+        from, to, days, weekly, monthly, by_source,
+    ))
+}
+
+pub async fn heatmap(
+    repo: &impl StatisticsRepository,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<Heatmap> {
+    validate_range(from, to)?;
+
+    let buckets = repo.hour_buckets(from, to).await?;
+
+    Ok(Heatmap::from_buckets(from, to, buckets))
+}
+
+pub fn validate_range(from: NaiveDate, to: NaiveDate) -> anyhow::Result<()> {
+    if from > to {
+        return Err(anyhow::anyhow!(
+            "Range start {} is after its end {}",
+            from,
+            to
+        ));
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(anyhow::anyhow!(
+            "Range may cover at most {} days",
+            MAX_RANGE_DAYS
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveTime, TimeDelta};
+    use chrono_tz::Pacific::Kiritimati;
+
+    use super::*;
+    use crate::{
+        clock::{at_local, LocalZone},
+        db::Database,
+        repository::{SessionRepository, SipRepository},
+    };
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    /// A wall clock time far from UTC, so local days differ from UTC's
+    fn local(date: NaiveDate, hour: u32, minute: u32) -> DateTime<LocalZone> {
+        let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+        at_local(&LocalZone::from(Kiritimati), date, time).unwrap()
+    }
+
+    /// A repository holding `sips` of (amount, local time, source)
+    async fn with_sips(sips: &[(i64, DateTime<LocalZone>, SipSource)]) -> SqliteRepository {
+        let repo = SqliteRepository::new(Database::in_memory().await.unwrap().pool);
+        let session_id = repo
+            .insert_session("test", local(date(1, 1), 8, 0).to_utc())
+            .await
+            .unwrap();
+        for &(amount, at, source) in sips {
+            repo.insert_sip(amount, session_id, None, at, source, None)
+                .await
+                .unwrap();
+        }
+        repo
+    }
+
+    fn summary(periods: &[PeriodAverage]) -> Vec<(NaiveDate, i64, i64, f64, i64)> {
+        periods
+            .iter()
+            .map(|period| {
+                (
+                    period.period_start,
+                    period.days,
+                    period.total_ml,
+                    period.average_ml,
+                    period.goal_met_days,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn days_without_sips_are_filled_with_zeroes() {
+        let repo = with_sips(&[
+            (300, local(date(6, 10), 9, 0), SipSource::Tray),
+            (200, local(date(6, 12), 23, 59), SipSource::Tray),
+        ])
+        .await;
+
+        let days = repo.daily_series(date(6, 9), date(6, 13)).await.unwrap();
+
+        assert_eq!(
+            days.iter()
+                .map(|day| (day.date, day.total_ml, day.sip_count))
+                .collect::<Vec<_>>(),
+            vec![
+                (date(6, 9), 0, 0),
+                (date(6, 10), 300, 1),
+                (date(6, 11), 0, 0),
+                (date(6, 12), 200, 1),
+                (date(6, 13), 0, 0),
+            ]
+        );
+        // Empty days are measured against the current goal
+        assert!(days.iter().all(|day| day.goal_ml == 2000 && !day.goal_met));
+    }
+
+    #[tokio::test]
+    async fn weekly_averages_divide_by_the_days_in_range() {
+        let repo = with_sips(&[
+            (400, local(date(6, 5), 9, 0), SipSource::Tray),
+            (200, local(date(6, 8), 21, 0), SipSource::Tray),
+            (2000, local(date(6, 9), 10, 0), SipSource::Tray),
+        ])
+        .await;
+
+        // Thursday to the Tuesday after
+        let weekly = repo.weekly_averages(date(6, 5), date(6, 10)).await.unwrap();
+
+        assert_eq!(
+            summary(&weekly),
+            vec![
+                (date(6, 2), 4, 600, 150.0, 0),
+                (date(6, 9), 2, 2000, 1000.0, 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn monthly_averages_split_at_local_month_ends() {
+        let repo = with_sips(&[
+            (300, local(date(5, 31), 23, 30), SipSource::Tray),
+            (500, local(date(6, 1), 0, 30), SipSource::Tray),
+        ])
+        .await;
+
+        let monthly = repo
+            .monthly_averages(date(5, 30), date(6, 2))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary(&monthly),
+            vec![
+                (date(5, 1), 2, 300, 150.0, 0),
+                (date(6, 1), 2, 500, 250.0, 0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn source_totals_cover_only_the_range() {
+        let repo = with_sips(&[
+            (100, local(date(6, 10), 0, 0), SipSource::Tray),
+            (250, local(date(6, 10), 12, 0), SipSource::MainWindow),
+            (100, local(date(6, 11), 23, 59), SipSource::Tray),
+            (900, local(date(6, 12), 0, 0), SipSource::Tray),
+        ])
+        .await;
+
+        let totals = repo.source_totals(date(6, 10), date(6, 11)).await.unwrap();
+
+        assert_eq!(
+            totals,
+            vec![
+                SourceTotal {
+                    source: SipSource::MainWindow,
+                    sip_count: 1,
+                    total_ml: 250,
+                },
+                SourceTotal {
+                    source: SipSource::Tray,
+                    sip_count: 2,
+                    total_ml: 200,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn statistics_average_over_every_day_of_the_range() {
+        let repo = with_sips(&[
+            (2400, local(date(6, 10), 9, 0), SipSource::Tray),
+            (600, local(date(6, 12), 9, 0), SipSource::Notification),
+        ])
+        .await;
+
+        let stats = statistics(&repo, date(6, 10), date(6, 13)).await.unwrap();
+
+        assert_eq!(stats.days.len(), 4);
+        assert_eq!(stats.total_ml, 3000);
+        assert_eq!(stats.average_ml, 750.0);
+        assert_eq!(stats.goal_completion_rate, 0.25);
+        // Days without sips are neither best nor worst
+        assert_eq!(stats.best_day.map(|day| day.date), Some(date(6, 10)));
+        assert_eq!(stats.worst_day.map(|day| day.date), Some(date(6, 12)));
+    }
+
+    #[tokio::test]
+    async fn statistics_without_sips_are_empty() {
+        let repo = with_sips(&[]).await;
+
+        let stats = statistics(&repo, date(6, 10), date(6, 10)).await.unwrap();
+
+        assert_eq!(stats.total_ml, 0);
+        assert_eq!(stats.average_ml, 0.0);
+        assert_eq!(stats.best_day, None);
+        assert!(stats.by_source.is_empty());
+    }
+
+    #[test]
+    fn ranges_must_run_forwards_and_stay_bounded() {
+        assert!(validate_range(date(6, 10), date(6, 10)).is_ok());
+        assert!(validate_range(date(6, 11), date(6, 10)).is_err());
+
+        let too_far = date(6, 10) + TimeDelta::days(MAX_RANGE_DAYS);
+        assert!(validate_range(date(6, 10), too_far).is_err());
+        assert!(validate_range(date(6, 10), too_far - TimeDelta::days(1)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn heatmap_buckets_by_local_weekday_and_hour() {
+        let repo = with_sips(&[
+            // Monday morning
+            (100, local(date(6, 9), 8, 15), SipSource::Tray),
+            (150, local(date(6, 9), 8, 45), SipSource::Tray),
+            (200, local(date(6, 9), 9, 0), SipSource::Tray),
+            // Late on Sunday, the last day of the range
+            (300, local(date(6, 15), 23, 30), SipSource::Tray),
+            // The Monday after
+            (900, local(date(6, 16), 0, 0), SipSource::Tray),
+        ])
+        .await;
+
+        let heatmap = heatmap(&repo, date(6, 9), date(6, 15)).await.unwrap();
+
+        assert_eq!(heatmap.volume_ml[0][8], 250);
+        assert_eq!(heatmap.sip_counts[0][8], 2);
+        assert_eq!(heatmap.volume_ml[0][9], 200);
+        assert_eq!(heatmap.volume_ml[6][23], 300);
+        assert_eq!(heatmap.volume_ml.iter().flatten().sum::<i64>(), 750);
+        assert_eq!(heatmap.sip_counts.iter().flatten().sum::<i64>(), 4);
+    }
+
+    #[test]
+    fn buckets_outside_the_grid_are_dropped() {
+        let bucket = |weekday, hour| HourBucket {
+            weekday,
+            hour,
+            total_ml: 100,
+            sip_count: 1,
+        };
+
+        let heatmap = Heatmap::from_buckets(
+            date(6, 9),
+            date(6, 15),
+            vec![bucket(0, 0), bucket(7, 0), bucket(1, 24), bucket(-1, 3)],
+        );
+
+        // Sunday is the last row
+        assert_eq!(heatmap.volume_ml[6][0], 100);
+        assert_eq!(heatmap.sip_counts.iter().flatten().sum::<i64>(), 1);
+    }
+}
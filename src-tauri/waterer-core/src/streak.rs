@@ -0,0 +1,298 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    future::Future,
+};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::{
+    clock::Clock,
+    repository::{DailyTotal, DailyTotalsRepository, SqliteRepository},
+};
+
+/// Runs of consecutive days that met the daily goal
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Streaks {
+    pub current_days: i64,
+    pub current_start: Option<NaiveDate>,
+    pub longest_days: i64,
+    pub longest_start: Option<NaiveDate>,
+    /// Whether today already counts towards the current streak
+    pub today_met: bool,
+}
+
+/// Storage for days excluded from streaks
+pub trait VacationRepository {
+    fn vacation_days(&self) -> impl Future<Output = Result<Vec<NaiveDate>, sqlx::Error>> + Send;
+
+    fn add_vacation_day(
+        &self,
+        date: NaiveDate,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    fn remove_vacation_day(
+        &self,
+        date: NaiveDate,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+}
+
+impl VacationRepository for SqliteRepository {
+    async fn vacation_days(&self) -> Result<Vec<NaiveDate>, sqlx::Error> {
+        sqlx::query_scalar::<_, NaiveDate>("SELECT date FROM vacation_days ORDER BY date")
+            .fetch_all(self.pool())
+            .await
+    }
+
+    async fn add_vacation_day(&self, date: NaiveDate) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO vacation_days (date) VALUES (?)",
+            date
+        )
+        .execute(self.pool())
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_vacation_day(&self, date: NaiveDate) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM vacation_days WHERE date = ?", date)
+            .execute(self.pool())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Whether a day's total is close enough to its goal
+fn goal_met(day: &DailyTotal, tolerance_percent: u8) -> bool {
+    let tolerance_percent = i64::from(tolerance_percent.min(100));
+    day.total_ml * 100 >= day.goal_ml * (100 - tolerance_percent)
+}
+
+/// Computes streaks from per-day totals. Vacation days are skipped: they
+/// neither extend nor break a streak. Today only breaks the current streak
+/// once it is over, so an unfinished today leaves yesterday's streak intact.
+pub fn compute_streaks(
+    days: &[DailyTotal],
+    vacation_days: &BTreeSet<NaiveDate>,
+    today: NaiveDate,
+    tolerance_percent: u8,
+) -> Streaks {
+    let met: HashMap<NaiveDate, bool> = days
+        .iter()
+        .map(|day| (day.date, goal_met(day, tolerance_percent)))
+        .collect();
+
+    let earliest = days
+        .iter()
+        .map(|day| day.date)
+        .chain(vacation_days.iter().copied())
+        .min();
+    let Some(earliest) = earliest.filter(|earliest| *earliest <= today) else {
+        return Streaks::default();
+    };
+
+    // None for vacation days, otherwise whether the goal was met
+    let qualifies = |date: NaiveDate| -> Option<bool> {
+        if vacation_days.contains(&date) {
+            None
+        } else {
+            Some(met.get(&date).copied().unwrap_or(false))
+        }
+    };
+
+    let today_met = qualifies(today) == Some(true);
+
+    let mut longest_days = 0;
+    let mut longest_start = None;
+    let mut run_days = 0;
+    let mut run_start = None;
+    for date in earliest.iter_days().take_while(|date| *date <= today) {
+        match qualifies(date) {
+            None => {}
+            Some(true) => {
+                if run_days == 0 {
+                    run_start = Some(date);
+                }
+                run_days += 1;
+                if run_days > longest_days {
+                    longest_days = run_days;
+                    longest_start = run_start;
+                }
+            }
+            // An unfinished today doesn't end the run
+            Some(false) if date == today => {}
+            Some(false) => {
+                run_days = 0;
+                run_start = None;
+            }
+        }
+    }
+
+    Streaks {
+        current_days: run_days,
+        current_start: run_start,
+        longest_days,
+        longest_start,
+        today_met,
+    }
+}
+
+pub async fn streaks(
+    repo: &(impl DailyTotalsRepository + VacationRepository),
+    clock: &dyn Clock,
+    tolerance_percent: u8,
+) -> Result<Streaks, sqlx::Error> {
+    let today = clock.local_now().date_naive();
+
+    // NaiveDate's default is 1970-01-01, well before any sip
+    let days = repo.daily_totals(NaiveDate::default(), today).await?;
+    let vacation_days = repo.vacation_days().await?.into_iter().collect();
+
+    Ok(compute_streaks(
+        &days,
+        &vacation_days,
+        today,
+        tolerance_percent,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveTime};
+    use chrono_tz::Pacific::Kiritimati;
+
+    use super::*;
+    use crate::{
+        clock::{at_local, LocalZone, ManualClock},
+        db::Database,
+        repository::{SessionRepository, SipRepository},
+        sip::SipSource,
+    };
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    fn total(d: u32, total_ml: i64) -> DailyTotal {
+        DailyTotal {
+            date: day(d),
+            total_ml,
+            sip_count: 1,
+            goal_ml: 2000,
+            goal_met: total_ml >= 2000,
+        }
+    }
+
+    fn vacation(days: &[u32]) -> BTreeSet<NaiveDate> {
+        days.iter().map(|&d| day(d)).collect()
+    }
+
+    #[test]
+    fn tolerance_lets_days_just_short_of_the_goal_count() {
+        let days = [total(9, 1900), total(10, 2000)];
+
+        let strict = compute_streaks(&days, &BTreeSet::new(), day(10), 4);
+        assert_eq!(
+            (strict.current_days, strict.current_start),
+            (1, Some(day(10)))
+        );
+
+        let tolerant = compute_streaks(&days, &BTreeSet::new(), day(10), 5);
+        assert_eq!(
+            (tolerant.current_days, tolerant.current_start),
+            (2, Some(day(9)))
+        );
+    }
+
+    #[test]
+    fn vacation_days_neither_extend_nor_break_a_streak() {
+        let days = [total(8, 2000), total(10, 2000), total(11, 2500)];
+
+        let streaks = compute_streaks(&days, &vacation(&[9]), day(11), 0);
+        assert_eq!(streaks.current_days, 3);
+        assert_eq!(streaks.current_start, Some(day(8)));
+
+        // Without the vacation the missed day ends the first run
+        let streaks = compute_streaks(&days, &BTreeSet::new(), day(11), 0);
+        assert_eq!(streaks.current_days, 2);
+        assert_eq!(streaks.longest_days, 2);
+        assert_eq!(streaks.longest_start, Some(day(10)));
+    }
+
+    #[test]
+    fn an_unfinished_today_keeps_the_streak_until_it_is_over() {
+        let days = [total(8, 2000), total(9, 2000), total(10, 500)];
+
+        let today = compute_streaks(&days, &BTreeSet::new(), day(10), 0);
+        assert_eq!(today.current_days, 2);
+        assert!(!today.today_met);
+
+        let tomorrow = compute_streaks(&days, &BTreeSet::new(), day(11), 0);
+        assert_eq!(tomorrow.current_days, 0);
+        assert_eq!(tomorrow.current_start, None);
+        assert_eq!(tomorrow.longest_days, 2);
+    }
+
+    #[test]
+    fn nothing_logged_is_no_streak() {
+        assert_eq!(
+            compute_streaks(&[], &BTreeSet::new(), day(10), 0),
+            Streaks::default()
+        );
+    }
+
+    /// A wall clock time in Kiritimati, fourteen hours ahead of UTC
+    fn local(d: u32, hour: u32) -> DateTime<LocalZone> {
+        let time = NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        at_local(&LocalZone::from(Kiritimati), day(d), time).unwrap()
+    }
+
+    #[tokio::test]
+    async fn edited_history_changes_the_streak() {
+        let repo = SqliteRepository::new(Database::in_memory().await.unwrap().pool);
+        let clock = ManualClock::new(local(12, 20).to_utc()).in_zone(Kiritimati);
+        let session_id = repo
+            .insert_session("test", local(10, 8).to_utc())
+            .await
+            .unwrap();
+        let mut sips = Vec::new();
+        for d in 10..=12 {
+            // Early mornings, still the day before in UTC
+            let sip_id = repo
+                .insert_sip(2000, session_id, None, local(d, 1), SipSource::Tray, None)
+                .await
+                .unwrap();
+            sips.push(sip_id);
+        }
+
+        let before = streaks(&repo, &clock, 0).await.unwrap();
+        assert_eq!(before.current_days, 3);
+        assert_eq!(before.current_start, Some(day(10)));
+        assert!(before.today_met);
+
+        // Less on the 11th breaks the run in two
+        sqlx::query("UPDATE sips SET amount = 1500 WHERE id = ?")
+            .bind(sips[1])
+            .execute(repo.pool())
+            .await
+            .unwrap();
+        let edited = streaks(&repo, &clock, 0).await.unwrap();
+        assert_eq!(edited.current_days, 1);
+        assert_eq!(edited.longest_days, 1);
+        assert_eq!(edited.longest_start, Some(day(10)));
+
+        // Unless the tolerance covers it
+        assert_eq!(streaks(&repo, &clock, 25).await.unwrap().current_days, 3);
+
+        sqlx::query("DELETE FROM sips WHERE id = ?")
+            .bind(sips[0])
+            .execute(repo.pool())
+            .await
+            .unwrap();
+        let deleted = streaks(&repo, &clock, 25).await.unwrap();
+        assert_eq!(deleted.current_days, 2);
+        assert_eq!(deleted.current_start, Some(day(11)));
+    }
+}
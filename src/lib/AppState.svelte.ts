@@ -11,12 +11,16 @@ type UpdateAppStateEventPayload = {
   timer_started: boolean;
   session_id: number;
   session_start: string;
+  run_id: number | null;
+  snoozed_until: string | null;
 };
 
 export class AppState implements TAppState {
   timerStarted: boolean = $state(false);
   sessionId: number = $state(0);
   sessionStart: string = $state("");
+  runId: number | null = $state(null);
+  snoozedUntil: string | null = $state(null);
 
   constructor() {
     this.updateAppState();
@@ -29,6 +33,8 @@ export class AppState implements TAppState {
           this.timerStarted = event.payload.timer_started;
           this.sessionId = event.payload.session_id;
           this.sessionStart = event.payload.session_start;
+          this.runId = event.payload.run_id;
+          this.snoozedUntil = event.payload.snoozed_until;
         }
       );
 
@@ -38,12 +44,22 @@ export class AppState implements TAppState {
     });
   }
 
+  async snooze(minutes?: number): Promise<void> {
+    try {
+      await invoke("snooze_reminder", { minutes });
+    } catch (err) {
+      console.error("Failed to snooze reminders:", err);
+    }
+  }
+
   async updateAppState(): Promise<void> {
     try {
       const result = await invoke<UpdateAppStateEventPayload>("get_app_state");
       this.timerStarted = result.timer_started;
       this.sessionId = result.session_id;
       this.sessionStart = result.session_start;
+      this.runId = result.run_id;
+      this.snoozedUntil = result.snoozed_until;
     } catch (err) {
       console.error("Failed to load app state:", err);
     }
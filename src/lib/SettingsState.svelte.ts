@@ -1,5 +1,11 @@
 import { invoke } from "@tauri-apps/api/core";
 import { getContext, setContext } from "svelte";
+import type {
+  MessageCatalog,
+  MessageTemplate,
+  NotificationChannel,
+  Sound,
+} from "./components";
 
 export interface Settings {
   sipAmountMl: number;
@@ -8,6 +14,66 @@ export interface Settings {
   notificationsEnabled: boolean;
   startMinimized: boolean;
   dailyGoalMl: number;
+  quietHoursStart?: string | null;
+  quietHoursEnd?: string | null;
+  pacingEnabled?: boolean;
+  anchorTimes?: string[];
+  streakTolerancePercent?: number;
+  goalMilestoneNotifications?: boolean;
+  maxHourlyIntakeMl?: number;
+  maxDailyIntakeMl?: number;
+  minSipGapSeconds?: number;
+  mergeDuplicateSips?: boolean;
+  timerAutoStart?: TimerAutoStart;
+  workingHoursStart?: string | null;
+  workingHoursEnd?: string | null;
+  pauseWhenAway?: boolean;
+  awayIdleMinutes?: number;
+  glassAmountMl?: number;
+  actionableNotifications?: boolean;
+  notificationRoutes?: NotificationRoutes;
+  notificationCommand?: string[];
+  locale?: string | null;
+  messageTemplates?: MessageTemplates;
+  soundVolumePercent?: number;
+  reminderSounds?: ReminderSounds;
+}
+
+export type TimerAutoStart = "restore" | "launch" | "working_hours";
+
+/** Channels tried in order for each kind of notification */
+export interface NotificationRoutes {
+  sip: NotificationChannel[];
+  welcomeBack: NotificationChannel[];
+  achievement: NotificationChannel[];
+  goal: NotificationChannel[];
+}
+
+/** Notification texts replacing the catalog's, see `MessageCatalog` */
+export interface MessageTemplates {
+  sip?: MessageTemplate | null;
+  welcomeBack?: MessageTemplate | null;
+  achievement?: MessageTemplate | null;
+  goalReached?: MessageTemplate | null;
+  goalMilestone?: MessageTemplate | null;
+}
+
+/**
+ * Sounds for each kind of notification by escalation step; the last one
+ * repeats for later steps and an empty list plays nothing
+ */
+export interface ReminderSounds {
+  sip: Sound[];
+  welcomeBack: Sound[];
+  achievement: Sound[];
+  goal: Sound[];
+}
+
+export interface SchedulePreview {
+  reminders: string[];
+  projected_total_ml: number;
+  daily_goal_ml: number;
+  projected_goal_met: boolean;
 }
 
 class SettingsState {
@@ -61,6 +127,30 @@ class SettingsState {
           notificationsEnabled: newSettings.notificationsEnabled,
           startMinimized: newSettings.startMinimized,
           dailyGoalMl: newSettings.dailyGoalMl,
+          notificationIntervalMinutes: newSettings.notificationIntervalMinutes,
+          quietHoursStart: newSettings.quietHoursStart,
+          quietHoursEnd: newSettings.quietHoursEnd,
+          pacingEnabled: newSettings.pacingEnabled,
+          anchorTimes: newSettings.anchorTimes,
+          streakTolerancePercent: newSettings.streakTolerancePercent,
+          goalMilestoneNotifications: newSettings.goalMilestoneNotifications,
+          maxHourlyIntakeMl: newSettings.maxHourlyIntakeMl,
+          maxDailyIntakeMl: newSettings.maxDailyIntakeMl,
+          minSipGapSeconds: newSettings.minSipGapSeconds,
+          mergeDuplicateSips: newSettings.mergeDuplicateSips,
+          timerAutoStart: newSettings.timerAutoStart,
+          workingHoursStart: newSettings.workingHoursStart,
+          workingHoursEnd: newSettings.workingHoursEnd,
+          pauseWhenAway: newSettings.pauseWhenAway,
+          awayIdleMinutes: newSettings.awayIdleMinutes,
+          glassAmountMl: newSettings.glassAmountMl,
+          actionableNotifications: newSettings.actionableNotifications,
+          notificationRoutes: newSettings.notificationRoutes,
+          notificationCommand: newSettings.notificationCommand,
+          locale: newSettings.locale,
+          messageTemplates: newSettings.messageTemplates,
+          soundVolumePercent: newSettings.soundVolumePercent,
+          reminderSounds: newSettings.reminderSounds,
         },
       });
 
@@ -78,6 +168,22 @@ class SettingsState {
     }
   }
 
+  async getMessageCatalogs() {
+    return invoke<MessageCatalog[]>("get_message_catalogs");
+  }
+
+  async getBundledSounds() {
+    return invoke<string[]>("get_bundled_sounds");
+  }
+
+  async previewSound(sound: Sound) {
+    return invoke<void>("preview_sound", { sound });
+  }
+
+  async previewSchedule() {
+    return invoke<SchedulePreview>("preview_schedule");
+  }
+
   updateSipAmount(amount: number) {
     this.settings.sipAmountMl = Math.max(1, Math.min(500, amount));
   }
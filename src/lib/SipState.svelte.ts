@@ -1,15 +1,27 @@
 import { getContext, setContext } from "svelte";
-import type { Sip } from "./components";
+import type {
+  GoalProgress,
+  ReminderFired,
+  Sip,
+  SipTotals,
+} from "./components";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+const RECENT_SIPS = 50;
 
 class SipState {
-  private interval: number | null = null;
+  /** The latest sips only, the full history is paged through `query_sips` */
   public sips: Sip[] = $state([]);
+  public totals: SipTotals | null = $state(null);
   public error: string = $state("");
   public loading: boolean = $state(true);
+  public goalReached: GoalProgress | null = $state(null);
+  public lastReminder: ReminderFired | null = $state(null);
 
+  public totalSips: number = $derived(this.totals?.total_sips_all_time ?? 0);
   public totalAmount: number = $derived(
-    this.sips.reduce((total, sip) => total + sip.amount, 0)
+    this.totals?.total_amount_all_time ?? 0
   );
 
   constructor() {
@@ -18,15 +30,31 @@ class SipState {
         this.loading = false;
       });
 
-      this.interval = setInterval(() => {
-        this.updateSips();
-      }, 1000);
+      // The backend pushes every change, so there is nothing to poll
+      const unlisteners = [
+        listen<Sip>("sip-logged", (event) => {
+          this.sips = [event.payload, ...this.sips].slice(0, RECENT_SIPS);
+        }),
+        listen<Sip>("sip-updated", (event) => {
+          this.sips = this.sips.map((sip) =>
+            sip.id === event.payload.id ? event.payload : sip
+          );
+        }),
+        listen<SipTotals>("totals-changed", (event) => {
+          this.totals = event.payload;
+        }),
+        listen<ReminderFired>("reminder-fired", (event) => {
+          this.lastReminder = event.payload;
+        }),
+        listen<GoalProgress>("goal-reached", (event) => {
+          this.goalReached = event.payload;
+        }),
+      ];
 
       // Cleanup function
       return () => {
-        if (this.interval) {
-          clearInterval(this.interval);
-          this.interval = null;
+        for (const unlistenFn of unlisteners) {
+          unlistenFn.then((unlisten) => unlisten());
         }
       };
     });
@@ -34,37 +62,41 @@ class SipState {
 
   async updateSips() {
     try {
-      const result = await invoke<Sip[]>("get_sips");
-      this.sips = result;
+      const [sips, totals] = await Promise.all([
+        invoke<Sip[]>("get_recent_sips", { limit: RECENT_SIPS }),
+        invoke<SipTotals>("get_sip_state"),
+      ]);
+      this.sips = sips;
+      this.totals = totals;
       this.error = ""; // Clear any previous errors
     } catch (err) {
       this.error = `Failed to load sips: ${err}`;
     }
   }
 
-  async takeSip(amount: number = 50) {
+  async takeSip(amount?: number) {
     try {
-      console.log("Taking sip of", amount, "ml");
-      const result = await invoke<Sip>("take_sip", { amount });
-      console.log("Sip taken:", result);
+      // Lets the backend recognise a retry of this very sip
+      const requestId = crypto.randomUUID();
 
-      // Refresh the sips list to get the updated state
-      await this.updateSips();
+      // Sips over the intake limits need an explicit confirmation
+      const warning = await invoke<string | null>("check_sip", { amount });
+      if (warning !== null && !confirm(`${warning}. Log it anyway?`)) {
+        return null;
+      }
 
-      return result;
+      return await invoke<SipTotals>("take_sip", {
+        amount,
+        source: "main_window",
+        requestId,
+        confirmed: warning !== null,
+      });
     } catch (err) {
       this.error = `Failed to take sip: ${err}`;
       console.error("Error taking sip:", err);
       throw err;
     }
   }
-
-  destroy() {
-    if (this.interval) {
-      clearInterval(this.interval);
-      this.interval = null;
-    }
-  }
 }
 
 const SIP_STATE_KEY = Symbol("SIP_STATE");
@@ -9,11 +9,198 @@ export { default as TimerIndicator } from "./TimerIndicator.svelte";
 export { default as Input } from "./Input.svelte";
 export { default as Select } from "./Select.svelte";
 export { default as ThemeToggle } from "./ThemeToggle.svelte";
+export { default as NotificationOverlay } from "./NotificationOverlay.svelte";
 
 // Type definitions for reuse
+export type SipSource =
+  | "tray"
+  | "main_window"
+  | "notification"
+  | "automation"
+  | "unknown";
+
 export interface Sip {
   id: number;
   amount: number;
   created_at: string;
   notified_user: boolean;
+  session_id: number;
+  run_id: number | null;
+  request_id: string | null;
+  source: SipSource;
+}
+
+/** Running totals kept by the backend, as returned by `get_sip_state` */
+export interface SipTotals {
+  last_sip_timestamp: number;
+  total_sips_today: number;
+  total_amount_today: number;
+  total_sips_all_time: number;
+  total_amount_all_time: number;
+  last_sip_id: number | null;
+  notified_user: boolean;
+  today: string | null;
+}
+
+export interface SipQuery {
+  from?: string;
+  to?: string;
+  sessionId?: number;
+  runId?: number;
+  source?: SipSource;
+  cursor?: number;
+  limit?: number;
+}
+
+export interface SipPage {
+  sips: Sip[];
+  next_cursor: number | null;
+  total_count: number;
+}
+
+export interface DailyTotal {
+  date: string;
+  total_ml: number;
+  sip_count: number;
+  goal_ml: number;
+  goal_met: boolean;
+}
+
+export interface PeriodAverage {
+  period_start: string;
+  days: number;
+  total_ml: number;
+  average_ml: number;
+  goal_met_days: number;
+}
+
+export interface SourceTotal {
+  source: SipSource;
+  sip_count: number;
+  total_ml: number;
+}
+
+export interface Statistics {
+  from: string;
+  to: string;
+  days: DailyTotal[];
+  weekly: PeriodAverage[];
+  monthly: PeriodAverage[];
+  by_source: SourceTotal[];
+  best_day: DailyTotal | null;
+  worst_day: DailyTotal | null;
+  total_ml: number;
+  average_ml: number;
+  goal_completion_rate: number;
+}
+
+/** Rows run Monday to Sunday, columns are the local hours 0 to 23 */
+export interface Heatmap {
+  from: string;
+  to: string;
+  volume_ml: number[][];
+  sip_counts: number[][];
+}
+
+export interface Streaks {
+  current_days: number;
+  current_start: string | null;
+  longest_days: number;
+  longest_start: string | null;
+  today_met: boolean;
+}
+
+export type AchievementCondition =
+  | { type: "daily_total_at_least"; ml: number }
+  | { type: "total_volume_at_least"; ml: number }
+  | { type: "total_sips_at_least"; count: number }
+  | { type: "streak_at_least"; days: number }
+  | { type: "sip_before"; time: string }
+  | { type: "earliest_first_sip"; min_days: number };
+
+export interface Achievement {
+  id: string;
+  title: string;
+  description: string;
+  condition: AchievementCondition;
+  unlocked_at: string | null;
+}
+
+export interface GoalProgress {
+  date: string;
+  total_ml: number;
+  goal_ml: number;
+  percent: number;
+}
+
+export interface ReminderFired {
+  fired_at: string;
+  last_sip_at: string | null;
+}
+
+export type SessionEndReason = "quit" | "crashed" | "update" | "unknown";
+
+export interface SessionSummary {
+  id: number;
+  session_id: string;
+  session_start: string;
+  session_end: string | null;
+  end_reason: SessionEndReason | null;
+  last_heartbeat: string | null;
+  duration_seconds: number;
+  sip_count: number;
+  total_ml: number;
+}
+
+export interface TimerRunSummary {
+  id: number;
+  session_id: number;
+  started_at: string;
+  stopped_at: string | null;
+  duration_seconds: number;
+  sip_count: number;
+  total_ml: number;
+}
+
+export type ReminderAction = "log_sip" | "log_glass" | "snooze" | "dismissed";
+
+export interface ReminderOutcome {
+  id: number;
+  fired_at: string;
+  action: ReminderAction;
+  acted_at: string;
+  sip_id: number | null;
+}
+
+export type NotificationKind = "sip" | "welcome_back" | "achievement" | "goal";
+
+export type NotificationChannel = "toast" | "sound" | "command" | "overlay";
+
+export interface Notification {
+  kind: NotificationKind;
+  title: string;
+  body: string;
+  step: number;
+}
+
+/** A bundled sound by name, or a WAV or OGG file */
+export type Sound =
+  | { type: "bundled"; name: string }
+  | { type: "file"; path: string };
+
+export interface MessageTemplate {
+  title: string;
+  body: string;
+}
+
+/** Bundled notification texts of one locale */
+export interface MessageCatalog {
+  locale: string;
+  name: string;
+  now: string;
+  sip: MessageTemplate;
+  welcome_back: MessageTemplate;
+  achievement: MessageTemplate;
+  goal_reached: MessageTemplate;
+  goal_milestone: MessageTemplate;
 }